@@ -1,16 +1,37 @@
 use regex::Regex;
 use semver::Version;
+use std::collections::HashSet;
 use std::env;
 use std::ffi::OsStr;
 use std::io::{self, ErrorKind};
 use std::path::{Path, PathBuf};
 use std::process::Command;
 
+#[path = "build/abi.rs"]
+mod abi;
+#[path = "build/cmake.rs"]
+mod cmake;
+#[cfg(feature = "bindgen")]
+#[path = "build/generate.rs"]
+mod generate;
+#[path = "build/report.rs"]
+mod report;
+#[path = "build/source.rs"]
+mod source;
+#[cfg(feature = "vendored")]
+#[path = "build/vendored.rs"]
+mod vendored;
+
+use report::Report;
+
 // Environment variables that can guide compilation
 
 /// A single path to search for LLVM in (containing bin/llvm-config)
 static ENV_LLVM_PREFIX: &str = "LLVM_SYS_FEATURED_PREFIX";
 
+/// The llvm-config binary to use, bypassing the search for one
+static ENV_LLVM_CONFIG: &str = "LLVM_SYS_FEATURED_CONFIG";
+
 /// If set, enforce precise correspondence between crate and binary versions.
 static ENV_STRICT_VERSIONING: &str = "LLVM_SYS_FEATURED_STRICT_VERSIONING";
 
@@ -23,10 +44,164 @@ static ENV_USE_DEBUG_MSVCRT: &str = "LLVM_SYS_FEATURED_USE_DEBUG_MSVCRT";
 /// If set, always link against libffi
 static ENV_FORCE_FFI: &str = "LLVM_SYS_FEATURED_FFI_WORKAROUND";
 
+/// If set, link against the shared LLVM library rather than the static
+/// component libraries, when a shared library is available
+static ENV_PREFER_DYNAMIC: &str = "LLVM_SYS_FEATURED_PREFER_DYNAMIC";
+
+/// The C++ standard library to link (`c++`, `stdc++`, ...), or `none`, if
+/// the one detected from LLVM's build is wrong
+static ENV_LIBCPP: &str = "LLVM_SYS_FEATURED_LIBCPP";
+
+/// When cross-compiling, the prefix of an LLVM built for the target (containing
+/// lib/ and include/), to link against instead of the host's LLVM libraries
+static ENV_TARGET_PREFIX: &str = "LLVM_SYS_FEATURED_TARGET_PREFIX";
+
+/// An llvm-config (or a wrapper script around one) that runs on the build host
+/// but describes the LLVM for the target; takes precedence over the search for
+/// the host's llvm-config
+static ENV_TARGET_LLVM_CONFIG: &str = "LLVM_SYS_FEATURED_TARGET_LLVM_CONFIG";
+
+/// If set, don't look for LLVM, and declare the bindings without building or
+/// linking anything, for checking code without LLVM installed (like the `stub`
+/// feature)
+static ENV_STUB: &str = "LLVM_SYS_FEATURED_STUB";
+
+/// With the `vendored` feature, an LLVM source tree (an llvm-project checkout
+/// or its llvm directory) to build if no installed LLVM is found
+static ENV_SOURCE_DIR: &str = "LLVM_SYS_FEATURED_SOURCE_DIR";
+
+/// With the `vendored` feature, a directory to keep LLVM builds in instead of
+/// OUT_DIR, which can be shared between projects
+static ENV_BUILD_CACHE: &str = "LLVM_SYS_FEATURED_BUILD_CACHE";
+
+/// How the LLVM libraries are linked.
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum LinkKind {
+    /// The static component libraries (libLLVMCore.a, libLLVMSupport.a, ...)
+    Static,
+    /// The single shared library (libLLVM-N.so, libLLVM.dylib, ...)
+    Dynamic,
+}
+
+impl LinkKind {
+    /// The flag that makes llvm-config answer for this kind of linking.
+    fn llvm_config_flag(self) -> &'static str {
+        match self {
+            LinkKind::Static => "--link-static",
+            LinkKind::Dynamic => "--link-shared",
+        }
+    }
+
+    /// The library kind to pass to `cargo:rustc-link-lib`.
+    fn rustc_link_kind(self) -> &'static str {
+        match self {
+            LinkKind::Static => "static",
+            LinkKind::Dynamic => "dylib",
+        }
+    }
+}
+
+/// Where we get information about the LLVM installation from.
+enum LlvmConfig {
+    /// An llvm-config binary
+    Binary(PathBuf),
+    /// LLVM's CMake package, for installations without llvm-config
+    CMake(cmake::LlvmPackage),
+}
+
+/// LLVM components that are always linked when the linked components are
+/// restricted with `component-*` features. These cover every module of this
+/// crate that isn't gated on an `LLVM_COMPONENT_*` cfg.
+static BASE_COMPONENTS: &[&str] = &[
+    "core",
+    "support",
+    "analysis",
+    "bitreader",
+    "bitwriter",
+    "irreader",
+    "linker",
+    "object",
+    "target",
+    "transformutils",
+    "scalaropts",
+    "instcombine",
+    "aggressiveinstcombine",
+    "vectorize",
+    "instrumentation",
+    "objcarcopts",
+    "codegen",
+    "remarks",
+    "mcdisassembler",
+];
+
+/// Optional LLVM components other than targets, as pairs of the
+/// `component-*` feature suffix and the llvm-config component name.
+static OPTIONAL_COMPONENTS: &[(&str, &str)] = &[
+    ("mcjit", "mcjit"),
+    ("interpreter", "interpreter"),
+    ("orc", "orcjit"),
+    ("lto", "lto"),
+    ("ipo", "ipo"),
+    ("coroutines", "coroutines"),
+];
+
+/// Target backends that can be selected with `component-*` features. The
+/// feature suffix and the llvm-config component name are the same, and match
+/// LLVM's own target name case-insensitively. Bindings for a backend are gated
+/// on its `LLVM_TARGET_*` cfg, which also requires that LLVM was built with it.
+static TARGET_COMPONENTS: &[&str] = &[
+    "aarch64",
+    "amdgpu",
+    "arm",
+    "bpf",
+    "hexagon",
+    "lanai",
+    "mips",
+    "msp430",
+    "nvptx",
+    "powerpc",
+    "riscv",
+    "sparc",
+    "systemz",
+    "webassembly",
+    "x86",
+    "xcore",
+];
+
 /// Filesystem path to an llvm-config binary for the specified
 /// (user-selected) LLVM version; or `None` if we failed to find an
-/// appropriate binary.
-fn get_llvm_config_path(selected_version: &Version) -> Option<PathBuf> {
+/// appropriate binary. Every binary tried is recorded in `report`.
+fn get_llvm_config_path(selected_version: &Version, report: &mut Report) -> Option<PathBuf> {
+    let requirement = version_requirement(selected_version);
+
+    // An llvm-config for the target, or one the user pinned, takes
+    // precedence over everything else.
+    for &var in &[ENV_TARGET_LLVM_CONFIG, ENV_LLVM_CONFIG] {
+        if let Some(path) = env::var_os(var) {
+            let pb = PathBuf::from(path);
+            return match llvm_version(&pb) {
+                Ok(ref version) if is_compatible_llvm(version, selected_version) => {
+                    report.add(format!("{} given by {}: LLVM {}, used", pb.display(), var, version));
+                    Some(pb)
+                }
+                Ok(version) => {
+                    report.add(format!(
+                        "{} given by {}: LLVM {}, rejected (need {})",
+                        pb.display(),
+                        var,
+                        version,
+                        requirement
+                    ));
+                    None
+                }
+                Err(e) => {
+                    report.add(format!("{} given by {}: {}", pb.display(), var, version_failure(&e)));
+                    None
+                }
+            };
+        }
+    }
+
     let llvm_config_binary_names: Vec<String> = vec![
         "llvm-config".into(),
         format!("llvm-config-{}", selected_version.major),
@@ -42,45 +217,148 @@ fn get_llvm_config_path(selected_version: &Version) -> Option<PathBuf> {
             pb.push("bin");
             pb.push(binary_name);
 
-            let version = llvm_version(&pb)
-                .unwrap_or_else(|_| panic!("Failed to execute {:?}", &pb));
-            if is_compatible_llvm(&version, selected_version) {
-                return Some(pb);
-            } else {
-                println!("LLVM binaries specified by {} are the wrong version.
-                            (Found {}, need {}.)", ENV_LLVM_PREFIX, version, selected_version);
+            match llvm_version(&pb) {
+                Ok(ref version) if is_compatible_llvm(version, selected_version) => {
+                    report.add(format!("{} (from {}): LLVM {}, used", pb.display(), ENV_LLVM_PREFIX, version));
+                    return Some(pb);
+                }
+                Ok(version) => report.add(format!(
+                    "{} (from {}): LLVM {}, rejected (need {})",
+                    pb.display(),
+                    ENV_LLVM_PREFIX,
+                    version,
+                    requirement
+                )),
+                // No llvm-config by this name; the prefix may have another
+                // one, or just the CMake package.
+                Err(ref e) if e.kind() == ErrorKind::NotFound => {
+                    report.add(format!("{} (from {}): not found", pb.display(), ENV_LLVM_PREFIX))
+                }
+                Err(e) => report.add(format!(
+                    "{} (from {}): {}",
+                    pb.display(),
+                    ENV_LLVM_PREFIX,
+                    version_failure(&e)
+                )),
             }
         }
         None
     } else {
-        // User didn't give us a path: try to find llvm-config via system PATH.
-        for binary_name in llvm_config_binary_names.iter() {
-            match llvm_version(binary_name) {
-                Ok(ref version) if is_compatible_llvm(version, selected_version) => {
-                    // Compatible version found. Nice.
-                    return Some(binary_name.into());
-                }
-                Ok(version) => {
-                    // Version mismatch. Will try further searches, but warn that
-                    // we're not using the system one.
-                    println!(
-                        "Found LLVM version {} on PATH, but need {}.",
-                        version, selected_version
-                    );
+        // User didn't give us a path: try every llvm-config we can find, on
+        // PATH and in the usual install locations, and take the one closest
+        // to the selected version.
+        let mut compatible = vec![];
+        for candidate in get_llvm_config_candidates(&llvm_config_binary_names) {
+            match llvm_version(&candidate) {
+                Ok(version) if is_compatible_llvm(&version, selected_version) => {
+                    compatible.push((version, candidate));
                 }
+                Ok(version) => report.add(format!(
+                    "{}: LLVM {}, rejected (need {})",
+                    candidate.display(),
+                    version,
+                    requirement
+                )),
                 Err(ref e) if e.kind() == ErrorKind::NotFound => {
-                    // Looks like we failed to execute any llvm-config. Keep
-                    // searching.
+                    let place = if candidate.is_relative() { " on PATH" } else { "" };
+                    report.add(format!("{}: not found{}", candidate.display(), place))
                 }
-                // Some other error, probably a weird failure. Give up.
-                Err(e) => panic!("Failed to search PATH for llvm-config: {}", e),
+                Err(e) => report.add(format!("{}: {}", candidate.display(), version_failure(&e))),
             }
         }
-        println!("Didn't find usable system-wide LLVM.");
-        None
+
+        // Every compatible version is at least the selected one, so the
+        // closest is the lowest. Ties go to the first found, so PATH wins.
+        let best = compatible
+            .iter()
+            .enumerate()
+            .min_by(|(_, (a, _)), (_, (b, _))| a.cmp(b))
+            .map(|(i, _)| i);
+        for (i, (version, candidate)) in compatible.iter().enumerate() {
+            let outcome = match best {
+                Some(best) if best == i => "used",
+                Some(best) if compatible[best].0 == *version => "compatible, but found later",
+                _ => "compatible, but not the closest",
+            };
+            report.add(format!("{}: LLVM {}, {}", candidate.display(), version, outcome));
+        }
+        best.map(|i| compatible.swap_remove(i).1)
+    }
+}
+
+/// Describe the LLVM versions that are compatible with the selected one.
+fn version_requirement(selected_version: &Version) -> String {
+    let strict =
+        cfg!(feature = "strict-versioning") || env::var_os(ENV_STRICT_VERSIONING).is_some();
+    if strict {
+        format!("{}.{}.x", selected_version.major, selected_version.minor)
+    } else {
+        format!("{} or newer", selected_version)
     }
 }
 
+/// Get the llvm-config binaries to consider: the given names on PATH, then
+/// any llvm-config in the usual install locations of distribution packages
+/// (`/usr/lib/llvm-N`), Homebrew (`/usr/local/opt/llvm@N`,
+/// `/opt/homebrew/opt/llvm@N`) and llvmenv.
+fn get_llvm_config_candidates(binary_names: &[String]) -> Vec<PathBuf> {
+    let mut candidates: Vec<PathBuf> = binary_names.iter().map(PathBuf::from).collect();
+
+    // Directories whose subdirectories matching a prefix may be LLVM prefixes
+    let mut roots: Vec<(PathBuf, &str)> = vec![
+        ("/usr/lib".into(), "llvm-"),
+        ("/usr/local/opt".into(), "llvm"),
+        ("/opt/homebrew/opt".into(), "llvm"),
+    ];
+    let data_dir = env::var_os("XDG_DATA_HOME")
+        .map(PathBuf::from)
+        .or_else(|| env::var_os("HOME").map(|home| Path::new(&home).join(".local").join("share")));
+    if let Some(data_dir) = data_dir {
+        roots.push((data_dir.join("llvmenv"), ""));
+    }
+    if let Some(home) = env::var_os("HOME") {
+        let app_support = Path::new(&home).join("Library").join("Application Support");
+        roots.push((app_support.join("llvmenv"), ""));
+    }
+
+    for (root, prefix) in roots {
+        let mut found: Vec<PathBuf> = match std::fs::read_dir(&root) {
+            Ok(entries) => entries
+                .filter_map(|entry| entry.ok())
+                .filter(|entry| entry.file_name().to_string_lossy().starts_with(prefix))
+                .map(|entry| entry.path().join("bin").join("llvm-config"))
+                .filter(|path| path.is_file())
+                .collect(),
+            Err(_) => continue,
+        };
+        found.sort();
+        candidates.extend(found);
+    }
+
+    // The same llvm-config is often found more than once, for instance on
+    // PATH and in /usr/lib/llvm-N, so only keep the first of each.
+    let mut seen = HashSet::new();
+    candidates.retain(|candidate| {
+        let path = if candidate.is_relative() {
+            find_on_path(candidate)
+        } else {
+            Some(candidate.clone())
+        };
+        match path.and_then(|path| std::fs::canonicalize(path).ok()) {
+            Some(path) => seen.insert(path),
+            None => true,
+        }
+    });
+    candidates
+}
+
+/// Find the binary called `name` in the directories on PATH, like the shell.
+fn find_on_path(name: &Path) -> Option<PathBuf> {
+    env::split_paths(&env::var_os("PATH")?)
+        .map(|dir| dir.join(name))
+        .find(|path| path.is_file())
+}
+
 /// Check whether the given LLVM version is compatible with the one selected via
 /// Cargo features.
 fn is_compatible_llvm(llvm_version: &Version, selected_version: &Version) -> bool {
@@ -97,37 +375,163 @@ fn is_compatible_llvm(llvm_version: &Version, selected_version: &Version) -> boo
     }
 }
 
+/// Check whether we are building for the given `target_os`.
+///
+/// The build script itself is compiled for the build host, so when
+/// cross-compiling `cfg!(target_os = ...)` would describe the host rather than
+/// the target. Cargo tells us about the target in the environment instead.
+fn target_os_is(os: &str) -> bool {
+    env::var("CARGO_CFG_TARGET_OS").ok().as_deref() == Some(os)
+}
+
+/// Check whether we are building for the given `target_env`. See
+/// `target_os_is`.
+fn target_env_is(target_env: &str) -> bool {
+    env::var("CARGO_CFG_TARGET_ENV").ok().as_deref() == Some(target_env)
+}
+
+/// Check whether we are cross-compiling, i.e. the target differs from the
+/// build host.
+fn is_cross_compiling() -> bool {
+    env::var("TARGET").ok() != env::var("HOST").ok()
+}
+
+/// Get the directory containing the LLVM libraries to link against.
+fn get_libdir(llvm: &LlvmConfig) -> String {
+    match env::var(ENV_TARGET_PREFIX) {
+        Ok(prefix) => Path::new(&prefix).join("lib").display().to_string(),
+        Err(_) => llvm_config(llvm, "--libdir").trim().to_owned(),
+    }
+}
+
+/// Get the directory containing LLVM's CMake package configuration.
+fn get_cmakedir(llvm: &LlvmConfig) -> PathBuf {
+    match env::var_os(ENV_TARGET_PREFIX) {
+        Some(prefix) => Path::new(&prefix).join("lib").join("cmake").join("llvm"),
+        None => PathBuf::from(llvm_config(llvm, "--cmakedir").trim()),
+    }
+}
+
+/// Get the directory containing the LLVM headers to compile against.
+fn get_includedir(llvm: &LlvmConfig) -> PathBuf {
+    match env::var_os(ENV_TARGET_PREFIX) {
+        Some(prefix) => Path::new(&prefix).join("include"),
+        None => PathBuf::from(llvm_config(llvm, "--includedir").trim()),
+    }
+}
+
 /// Get the output from running `llvm-config` with the given argument.
-fn llvm_config(llvm_config_path: &Path, arg: &str) -> String {
-    llvm_config_ex(llvm_config_path, arg)
-        .expect("Surprising failure from llvm-config")
+fn llvm_config(llvm: &LlvmConfig, arg: &str) -> String {
+    llvm_config_linked(llvm, &[arg], LinkKind::Static)
+}
+
+/// Get the output from running `llvm-config` with the given argument, for
+/// queries about how LLVM was built (`--targets-built`, `--has-rtti`, ...).
+///
+/// When cross-compiling against `LLVM_SYS_FEATURED_TARGET_PREFIX` without a
+/// target llvm-config, llvm-config describes the host's LLVM, so the target
+/// LLVM's CMake package answers these instead, if it has one.
+fn llvm_build_setting(llvm: &LlvmConfig, arg: &str) -> String {
+    if let LlvmConfig::Binary(_) = llvm {
+        if env::var_os(ENV_TARGET_PREFIX).is_some() && env::var_os(ENV_TARGET_LLVM_CONFIG).is_none()
+        {
+            if let Some(package) = cmake::read_package(&get_cmakedir(llvm)) {
+                return package.query(&[arg], LinkKind::Static);
+            }
+        }
+    }
+    llvm_config(llvm, arg)
+}
+
+/// Get the output from running `llvm-config` with the given argument, for
+/// queries whose answer depends on how LLVM is linked (`--libnames`,
+/// `--system-libs`, ...).
+///
+/// Without an llvm-config binary, the CMake package answers instead.
+fn llvm_config_linked(llvm: &LlvmConfig, args: &[&str], link_kind: LinkKind) -> String {
+    match llvm {
+        LlvmConfig::Binary(path) => llvm_config_ex(path, args, link_kind)
+            .expect("Surprising failure from llvm-config"),
+        LlvmConfig::CMake(package) => package.query(args, link_kind),
+    }
 }
 
 /// Invoke the specified binary as llvm-config.
 ///
 /// Explicit version of the `llvm_config` function that bubbles errors
 /// up.
-fn llvm_config_ex(binary: impl AsRef<OsStr>, arg: &str) -> io::Result<String> {
-    Command::new(binary)
-        .arg(arg)
-        .arg("--link-static") // Don't use dylib for >= 3.9
-        .output()
-        .map(|output| {
-            String::from_utf8(output.stdout).expect("Output from llvm-config was not valid UTF-8")
-        })
+fn llvm_config_ex(
+    binary: impl AsRef<OsStr>,
+    args: &[&str],
+    link_kind: LinkKind,
+) -> io::Result<String> {
+    let output = Command::new(binary)
+        .args(args)
+        .arg(link_kind.llvm_config_flag())
+        .output()?;
+    String::from_utf8(output.stdout).map_err(|_| {
+        io::Error::new(
+            ErrorKind::InvalidData,
+            "output from llvm-config was not valid UTF-8",
+        )
+    })
 }
 
-/// Get the LLVM version using llvm-config.
+/// Decide whether to link LLVM statically or dynamically.
+///
+/// Static linking is the default. Dynamic linking can be requested with the
+/// `prefer-dynamic` feature or the `LLVM_SYS_FEATURED_PREFER_DYNAMIC`
+/// environment variable, but we fall back to static linking if this LLVM
+/// wasn't built with a shared library.
+fn get_link_kind(llvm: &LlvmConfig) -> LinkKind {
+    let prefer_dynamic =
+        cfg!(feature = "prefer-dynamic") || env::var_os(ENV_PREFER_DYNAMIC).is_some();
+    if !prefer_dynamic {
+        return LinkKind::Static;
+    }
+
+    // With --link-shared, --shared-mode reports "shared" if the shared library
+    // exists, and fails (or reports "static") otherwise.
+    let has_shared_library = match llvm {
+        LlvmConfig::Binary(path) => Command::new(path)
+            .arg("--shared-mode")
+            .arg(LinkKind::Dynamic.llvm_config_flag())
+            .output()
+            .map(|output| {
+                output.status.success()
+                    && String::from_utf8_lossy(&output.stdout).trim() == "shared"
+            })
+            .unwrap_or(false),
+        LlvmConfig::CMake(package) => {
+            package.query(&["--shared-mode"], LinkKind::Dynamic).trim() == "shared"
+        }
+    };
+    if has_shared_library {
+        LinkKind::Dynamic
+    } else {
+        println!("cargo:warning=Dynamic linking was requested, but this LLVM has no shared library. Linking statically instead.");
+        LinkKind::Static
+    }
+}
+
+/// Get the LLVM version using llvm-config. Output that isn't a version is
+/// an error of kind `InvalidData`.
 fn llvm_version(binary: impl AsRef<OsStr>) -> io::Result<Version> {
-    let version_str = llvm_config_ex(binary.as_ref(), "--version")?;
+    let version_str = llvm_config_ex(binary.as_ref(), &["--version"], LinkKind::Static)?;
 
     // LLVM isn't really semver and uses version suffixes to build
     // version strings like '3.8.0svn', so limit what we try to parse
     // to only the numeric bits.
     let re = Regex::new(r"^(?P<major>\d+)\.(?P<minor>\d+)(?:\.(?P<patch>\d+))??").unwrap();
-    let c = re
-        .captures(&version_str)
-        .expect("Could not determine LLVM version from llvm-config.");
+    let c = re.captures(&version_str).ok_or_else(|| {
+        io::Error::new(
+            ErrorKind::InvalidData,
+            format!(
+                "could not determine the LLVM version from {:?}",
+                version_str.trim()
+            ),
+        )
+    })?;
 
     // some systems don't have a patch number but Version wants it so we just append .0 if it isn't
     // there
@@ -138,74 +542,259 @@ fn llvm_version(binary: impl AsRef<OsStr>) -> io::Result<Version> {
     Ok(Version::parse(&s).unwrap())
 }
 
+/// Describe why `llvm_version` failed, for the report.
+fn version_failure(e: &io::Error) -> String {
+    match e.kind() {
+        ErrorKind::InvalidData => e.to_string(),
+        _ => format!("failed to execute ({})", e),
+    }
+}
+
 /// Get the names of the dylibs required by LLVM, including the C++ standard
 /// library.
-fn get_system_libraries(llvm_config_path: &Path) -> Vec<String> {
-    llvm_config(llvm_config_path, "--system-libs")
+fn get_system_libraries(
+    llvm: &LlvmConfig,
+    link_kind: LinkKind,
+    components: &[&str],
+) -> Vec<String> {
+    let mut args = vec!["--system-libs"];
+    args.extend_from_slice(components);
+    llvm_config_linked(llvm, &args, link_kind)
         .split(&[' ', '\n'] as &[char])
         .filter(|s| !s.is_empty())
         .map(|flag| {
-            if cfg!(target_env = "msvc") {
+            if Path::new(flag).is_absolute() {
+                // A library outside the default search paths, e.g.
+                // /usr/lib/x86_64-linux-gnu/libz3.so
+                system_library_from_path(flag)
+            } else if target_env_is("msvc") {
                 // Same as --libnames, foo.lib
                 assert!(flag.ends_with(".lib"));
-                &flag[..flag.len() - 4]
-            } else if cfg!(target_os = "macos") {
+                flag[..flag.len() - 4].to_owned()
+            } else if target_os_is("macos") {
                 // Linker flags style, -lfoo
                 assert!(flag.starts_with("-l"));
                 if flag.ends_with(".tbd") && flag.starts_with("-llib") {
-                    &flag[5..flag.len() - 4]
+                    flag[5..flag.len() - 4].to_owned()
                 } else {
-                    &flag[2..]
+                    flag[2..].to_owned()
                 }
             } else {
                 // Linker flags style, -lfoo
                 assert!(flag.starts_with("-l"));
-                &flag[2..]
+                flag[2..].to_owned()
             }
         })
-        .chain(get_system_libcpp())
-        .map(str::to_owned)
+        .chain(get_system_libcpp(llvm))
         .collect::<Vec<String>>()
 }
 
+/// Get the name to link a system library given by its path, and add its
+/// directory to the library search path.
+fn system_library_from_path(path: &str) -> String {
+    let path = Path::new(path);
+    if let Some(dir) = path.parent() {
+        println!("cargo:rustc-link-search=native={}", dir.display());
+    }
+    // libz3.so.4 -> z3, zlib.lib -> zlib
+    let file_name = path
+        .file_name()
+        .and_then(OsStr::to_str)
+        .expect("Unexpected system library path from llvm-config");
+    let stem = file_name.split('.').next().unwrap();
+    if target_env_is("msvc") {
+        stem.to_owned()
+    } else {
+        stem.trim_start_matches("lib").to_owned()
+    }
+}
+
+/// Settings of the LLVM build that decide which system libraries it needs,
+/// and other properties of the build worth knowing about.
+#[derive(Debug, Default)]
+struct LlvmBuildConfig {
+    /// `LLVM_ENABLE_FFI`: the interpreter calls external functions through
+    /// libffi.
+    ffi: bool,
+    /// `LLVM_ENABLE_ZLIB`: Support can compress sections with zlib.
+    zlib: bool,
+    /// `LLVM_ENABLE_TERMINFO`: Support queries terminal colors via terminfo.
+    terminfo: bool,
+    /// `LLVM_ENABLE_LIBXML2`: WindowsManifest uses libxml2.
+    libxml2: bool,
+    /// `llvm-config --has-rtti`
+    rtti: bool,
+    /// `llvm-config --assertion-mode`
+    assertions: bool,
+}
+
+impl LlvmBuildConfig {
+    /// Get the system libraries this LLVM needs for the given components (or
+    /// all of LLVM, if `components` is empty) which are not among `linked`.
+    ///
+    /// `llvm-config --system-libs` doesn't report some of them, libffi in
+    /// particular, so this fills the gaps from the build settings.
+    fn missing_system_libraries(
+        &self,
+        components: &[&str],
+        linked: &[String],
+    ) -> Vec<&'static str> {
+        let is_linked = |names: &[&str]| linked.iter().any(|l| names.contains(&&**l));
+        let mut missing = vec![];
+        if self.ffi
+            && (components.is_empty() || components.contains(&"interpreter"))
+            && !is_linked(&["ffi"])
+        {
+            missing.push("ffi");
+        }
+        if self.zlib && !is_linked(&["z"]) {
+            missing.push("z");
+        }
+        if self.terminfo && !is_linked(&["tinfo", "terminfo", "curses", "ncurses", "ncursesw"]) {
+            missing.push("tinfo");
+        }
+        if self.libxml2 && components.is_empty() && !is_linked(&["xml2"]) {
+            missing.push("xml2");
+        }
+        missing
+    }
+}
+
+/// Read the settings LLVM was built with from `LLVMConfig.cmake` and
+/// llvm-config.
+fn get_llvm_build_config(llvm: &LlvmConfig) -> LlvmBuildConfig {
+    let mut config = LlvmBuildConfig {
+        rtti: llvm_build_setting(llvm, "--has-rtti").trim() == "YES",
+        assertions: llvm_build_setting(llvm, "--assertion-mode").trim() == "ON",
+        ..Default::default()
+    };
+
+    let llvm_config_cmake = get_cmakedir(llvm).join("LLVMConfig.cmake");
+    let variables = match cmake::read_variables(&llvm_config_cmake, Default::default()) {
+        Some(variables) => variables,
+        None => {
+            println!(
+                "cargo:warning=Didn't find {}; relying on llvm-config --system-libs alone.",
+                llvm_config_cmake.display()
+            );
+            return config;
+        }
+    };
+    let setting = |name: &str| matches!(variables.get(name), Some(value) if is_cmake_true(value));
+    config.ffi = setting("LLVM_ENABLE_FFI");
+    config.zlib = setting("LLVM_ENABLE_ZLIB");
+    config.terminfo = setting("LLVM_ENABLE_TERMINFO");
+    config.libxml2 = setting("LLVM_ENABLE_LIBXML2");
+    config
+}
+
+/// Check whether a CMake value counts as true in an `if()`.
+fn is_cmake_true(value: &str) -> bool {
+    match &*value.to_uppercase() {
+        "1" | "ON" | "YES" | "TRUE" | "Y" => true,
+        other => matches!(other.parse::<f64>(), Ok(n) if n != 0.0),
+    }
+}
+
 /// Get the library that must be linked for C++, if any.
-fn get_system_libcpp() -> Option<&'static str> {
-    if cfg!(target_env = "msvc") {
+///
+/// In order of preference, this is what the user asked for with
+/// `LLVM_SYS_FEATURED_LIBCPP`, what LLVM was built with according to
+/// `llvm-config --cxxflags` or its libraries, or the platform's usual C++
+/// standard library.
+fn get_system_libcpp(llvm: &LlvmConfig) -> Option<String> {
+    if let Ok(libcpp) = env::var(ENV_LIBCPP) {
+        return match &*libcpp {
+            "none" => None,
+            "c++" | "stdc++" | "c++_shared" | "c++_static" => Some(libcpp),
+            _ => panic!(
+                "llvm-sys-featured: {} must be one of `c++`, `stdc++`, `c++_shared`, \
+                 `c++_static` or `none`, not `{}`.",
+                ENV_LIBCPP, libcpp
+            ),
+        };
+    }
+
+    if target_env_is("msvc") {
         // MSVC doesn't need an explicit one.
-        None
-    } else if cfg!(target_os = "macos") {
+        return None;
+    }
+
+    // An explicit -stdlib flag settles it.
+    let cxxflags = llvm_config(llvm, "--cxxflags");
+    match cxxflags.split_whitespace().find(|flag| flag.starts_with("-stdlib=")) {
+        Some("-stdlib=libc++") => return Some("c++".into()),
+        Some("-stdlib=libstdc++") => return Some("stdc++".into()),
+        _ => {}
+    }
+
+    // Otherwise look at what LLVMSupport was compiled against: libc++ puts
+    // everything in the inline namespace std::__1, which mangles as St3__1.
+    let support = Path::new(&get_libdir(llvm)).join(if target_env_is("msvc") {
+        "LLVMSupport.lib"
+    } else {
+        "libLLVMSupport.a"
+    });
+    if let Ok(bytes) = std::fs::read(&support) {
+        let uses_libcpp = bytes.windows(6).any(|window| window == b"St3__1");
+        return Some(if uses_libcpp { "c++" } else { "stdc++" }.into());
+    }
+
+    if target_os_is("macos") {
         // On OS X 10.9 and later, LLVM's libc++ is the default. On earlier
-        // releases GCC's libstdc++ is default. Unfortunately we can't
-        // reasonably detect which one we need (on older ones libc++ is
-        // available and can be selected with -stdlib=lib++), so assume the
-        // latest, at the cost of breaking the build on older OS releases
-        // when LLVM was built against libstdc++.
-        Some("c++")
-    } else if cfg!(target_os = "freebsd") {
-        Some("c++")
+        // releases GCC's libstdc++ is default. If we couldn't tell from the
+        // LLVM libraries, assume the latest, at the cost of breaking the
+        // build on older OS releases when LLVM was built against libstdc++.
+        Some("c++".into())
+    } else if target_os_is("freebsd") {
+        Some("c++".into())
     } else {
         // Otherwise assume GCC's libstdc++.
         // This assumption is probably wrong on some platforms, but would need
-        // testing on them.
-        Some("stdc++")
+        // testing on them; LLVM_SYS_FEATURED_LIBCPP covers those.
+        Some("stdc++".into())
     }
 }
 
 /// Get the names of libraries to link against.
-fn get_link_libraries(llvm_config_path: &Path) -> Vec<String> {
+///
+/// If `components` is empty, this is every LLVM library.
+fn get_link_libraries(
+    llvm: &LlvmConfig,
+    link_kind: LinkKind,
+    components: &[&str],
+) -> Vec<String> {
     // Using --libnames in conjunction with --libdir is particularly important
     // for MSVC when LLVM is in a path with spaces, but it is generally less of
     // a hack than parsing linker flags output from --libs and --ldflags.
-    llvm_config(llvm_config_path, "--libnames")
+    let mut args = vec!["--libnames"];
+    args.extend_from_slice(components);
+    let libnames = llvm_config_linked(llvm, &args, link_kind);
+    if libnames.trim().is_empty() {
+        panic!(
+            "llvm-config did not list any libraries for components {:?}; \
+             are they all available in this LLVM?",
+            components
+        );
+    }
+    libnames
         .split(&[' ', '\n'] as &[char])
         .filter(|s| !s.is_empty())
         .map(|name| {
             // --libnames gives library filenames. Extract only the name that
             // we need to pass to the linker.
-            if cfg!(target_env = "msvc") {
+            if target_env_is("msvc") {
                 // LLVMfoo.lib
                 assert!(name.ends_with(".lib"));
                 &name[..name.len() - 4]
+            } else if link_kind == LinkKind::Dynamic {
+                // libLLVM-10.so, libLLVM.so.10, libLLVM.dylib
+                assert!(name.starts_with("lib"));
+                let end = name
+                    .find(".so")
+                    .or_else(|| name.find(".dylib"))
+                    .expect("Unexpected shared library name from llvm-config");
+                &name[3..end]
             } else {
                 // libLLVMfoo.a
                 assert!(name.starts_with("lib") && name.ends_with(".a"));
@@ -216,8 +805,18 @@ fn get_link_libraries(llvm_config_path: &Path) -> Vec<String> {
         .collect::<Vec<String>>()
 }
 
-fn get_llvm_cflags(llvm_config_path: &Path) -> String {
-    let output = llvm_config(llvm_config_path, "--cflags");
+fn get_llvm_cflags(llvm: &LlvmConfig) -> String {
+    let mut output = llvm_config(llvm, "--cflags");
+
+    // When cross-compiling against a separate target LLVM, point the include
+    // path at its headers instead of the ones llvm-config knows about.
+    if env::var_os(ENV_TARGET_PREFIX).is_some() {
+        let includedir = llvm_config(llvm, "--includedir");
+        output = output.replace(
+            includedir.trim(),
+            &get_includedir(llvm).display().to_string(),
+        );
+    }
 
     // llvm-config includes cflags from its own compilation with --cflags that
     // may not be relevant to us. In particularly annoying cases, these might
@@ -225,26 +824,221 @@ fn get_llvm_cflags(llvm_config_path: &Path) -> String {
     // using. Unless requested otherwise, clean CFLAGS of options that are
     // known to be possibly-harmful.
     let no_clean = env::var_os(&*ENV_NO_CLEAN_CFLAGS).is_some();
-    if no_clean || cfg!(target_env = "msvc") {
+    if no_clean || target_env_is("msvc") {
         // MSVC doesn't accept -W... options, so don't try to strip them and
         // possibly strip something that should be retained. Also do nothing if
         // the user requests it.
         return output;
     }
 
-    llvm_config(llvm_config_path, "--cflags")
+    output
         .split(&[' ', '\n'][..])
         .filter(|word| !word.starts_with("-W"))
         .collect::<Vec<_>>()
         .join(" ")
 }
 
-fn is_llvm_debug(llvm_config_path: &Path) -> bool {
+fn is_llvm_debug(llvm: &LlvmConfig) -> bool {
     // Has to be either Debug or Release
-    llvm_config(llvm_config_path, "--build-mode").contains("Debug")
+    llvm_build_setting(llvm, "--build-mode").contains("Debug")
+}
+
+/// Get the names of the target backends this LLVM was built with, as LLVM
+/// spells them (`AArch64`, `X86`, ...).
+fn get_targets_built(llvm: &LlvmConfig) -> Vec<String> {
+    llvm_build_setting(llvm, "--targets-built")
+        .split_whitespace()
+        .map(str::to_owned)
+        .collect()
+}
+
+/// Get the `component-*` features that are enabled, by feature suffix.
+fn get_selected_components() -> Vec<&'static str> {
+    OPTIONAL_COMPONENTS
+        .iter()
+        .map(|&(feature, _)| feature)
+        .chain(TARGET_COMPONENTS.iter().cloned())
+        .filter(|feature| {
+            env::var_os(format!("CARGO_FEATURE_COMPONENT_{}", feature.to_uppercase())).is_some()
+        })
+        .collect()
+}
+
+/// Write copies of LLVM's target lists (`llvm/Config/Targets.def` and
+/// friends) to `out_dir`, keeping only the given targets, so that the
+/// `LLVMInitializeAll*` wrappers don't reference backends we don't link.
+///
+/// Returns whether the native target is among them.
+fn write_target_lists(llvm: &LlvmConfig, targets: &[String], out_dir: &Path) -> bool {
+    let includedir = get_includedir(llvm);
+    let config_dir = out_dir.join("llvm").join("Config");
+    std::fs::create_dir_all(&config_dir).expect("Failed to create target list directory");
+
+    let entry = Regex::new(r"^LLVM_[A-Z_]+\((?P<target>\w+)\)").unwrap();
+    for list in &["Targets.def", "AsmPrinters.def", "AsmParsers.def", "Disassemblers.def"] {
+        let original = std::fs::read_to_string(includedir.join("llvm").join("Config").join(list))
+            .unwrap_or_else(|e| panic!("Failed to read LLVM target list {}: {}", list, e));
+        let filtered = original
+            .lines()
+            .filter(|line| match entry.captures(line) {
+                Some(c) => targets.contains(&c["target"].to_lowercase()),
+                None => true,
+            })
+            .collect::<Vec<_>>()
+            .join("\n");
+        std::fs::write(config_dir.join(list), filtered)
+            .unwrap_or_else(|e| panic!("Failed to write LLVM target list {}: {}", list, e));
+    }
+
+    let llvm_config_h = includedir.join("llvm").join("Config").join("llvm-config.h");
+    let native_arch = Regex::new(r"(?m)^#define LLVM_NATIVE_ARCH (?P<arch>\w+)").unwrap();
+    std::fs::read_to_string(&llvm_config_h)
+        .ok()
+        .and_then(|header| {
+            native_arch
+                .captures(&header)
+                .map(|c| targets.contains(&c["arch"].to_lowercase()))
+        })
+        .unwrap_or(false)
+}
+
+/// Write the names of the functions in the bindings that are enabled by
+/// `cfgs` to `out_dir`, for the runtime-loading table in `src/runtime.rs`.
+/// Some are marked as optional, since the library that is loaded may not have
+/// them:
+///
+/// - the initialization functions of target backends it wasn't built with;
+/// - the functions of `libLTO` and `libRemarks`, which are separate libraries
+///   that `libLLVM` doesn't necessarily export these from;
+/// - functions that later versions of LLVM removed (those declared only up to
+///   some version), so that a newer LLVM can be loaded.
+fn write_runtime_symbols(cfgs: &[String], out_dir: &Path) {
+    let src_dir = PathBuf::from(env::var_os("CARGO_MANIFEST_DIR").unwrap()).join("src");
+    println!("cargo:rerun-if-changed={}", src_dir.display());
+
+    let mut symbols = vec![];
+    for (module, file) in source::find_modules(&src_dir, cfgs) {
+        let module_optional = module == "crate::lto" || module == "crate::link_time_optimizer";
+        let source = std::fs::read_to_string(&file)
+            .unwrap_or_else(|e| panic!("Failed to read {}: {}", file.display(), e));
+        for function in source::extern_functions(&source, cfgs) {
+            let is_target = function
+                .block_attributes
+                .iter()
+                .any(|a| a.starts_with("#[cfg(LLVM_TARGET_"));
+            let is_removed = function
+                .block_attributes
+                .iter()
+                .chain(&function.attributes)
+                .any(|a| a.contains("_OR_LOWER"));
+            let optional = module_optional
+                || is_target
+                || is_removed
+                || function.name == "LLVMRemarkVersion";
+            symbols.push((function.name, !optional));
+        }
+    }
+    symbols.sort();
+    symbols.dedup();
+
+    let mut contents = String::from(
+        "// Generated by the build script from the bindings; see write_runtime_symbols.\n\
+         static SYMBOLS: &[(&str, bool)] = &[\n",
+    );
+    for (name, required) in &symbols {
+        contents.push_str(&format!("    (\"{}\\0\", {}),\n", name, required));
+    }
+    contents.push_str("];\n");
+    std::fs::write(out_dir.join("runtime_symbols.rs"), contents)
+        .expect("Failed to write the list of functions for runtime loading");
+}
+
+/// Get all the cfgs the crate is compiled with: `cfgs` (those we set), plus
+/// those Cargo sets for the enabled features and the target, in the form
+/// `source::attributes_enabled` expects.
+fn get_active_cfgs(cfgs: &[String]) -> Vec<String> {
+    let mut active_cfgs = cfgs.to_vec();
+    for (name, value) in env::vars() {
+        if let Some(feature) = name.strip_prefix("CARGO_FEATURE_") {
+            // All our features are spelled with hyphens.
+            active_cfgs.push(format!("feature=\"{}\"", feature.to_lowercase().replace('_', "-")));
+        } else if let Some(cfg) = name.strip_prefix("CARGO_CFG_") {
+            let cfg = cfg.to_lowercase();
+            if value.is_empty() {
+                active_cfgs.push(cfg);
+            } else {
+                active_cfgs.extend(value.split(',').map(|v| format!("{}=\"{}\"", cfg, v)));
+            }
+        }
+    }
+    active_cfgs
+}
+
+/// Export our cfgs to the build scripts of the crates that depend on this one
+/// directly, as DEP_LLVM_CFGS, with every cfg that we may set as
+/// DEP_LLVM_CHECK_CFGS, for them to declare. `targets` are the target backends
+/// this LLVM has. The `llvm-sys-featured-cfg` crate sets the same cfgs for
+/// the crate whose build script calls it.
+fn export_cfgs(cfgs: &[String], targets: &[String]) {
+    println!("cargo:cfgs={}", cfgs.join(" ")); // DEP_LLVM_CFGS
+    println!("cargo:check_cfgs={}", check_cfgs(targets).join(" ")); // DEP_LLVM_CHECK_CFGS
+}
+
+/// Declare every cfg that we may set for this crate, so that rustc doesn't
+/// warn about the `#[cfg]`s on the ones that aren't set. This is done first
+/// thing, as the build can stop early in several ways.
+fn declare_cfgs() {
+    let mut cfgs = check_cfgs(&[]);
+    // Only for this crate's own use
+    cfgs.push("LLVM_SYS_STUB".to_owned());
+    cfgs.push("LLVM_SYS_NOT_FOUND".to_owned());
+    for cfg in cfgs {
+        println!("cargo:rustc-check-cfg=cfg({})", cfg);
+    }
+}
+
+/// Every cfg that we may set and that dependents may use. `targets` are the
+/// target backends this LLVM has, in case it has any we don't know of.
+fn check_cfgs(targets: &[String]) -> Vec<String> {
+    let mut check_cfgs = vec![];
+    check_cfgs.extend((9..=15).map(|major| format!("LLVM_VERSION_{}_OR_GREATER", major)));
+    check_cfgs.extend((8..=14).map(|major| format!("LLVM_VERSION_{}_OR_LOWER", major)));
+    check_cfgs.extend(
+        OPTIONAL_COMPONENTS
+            .iter()
+            .map(|&(feature, _)| format!("LLVM_COMPONENT_{}", feature.to_uppercase())),
+    );
+    let mut target_names: Vec<String> = TARGET_COMPONENTS
+        .iter()
+        .map(|target| target.to_uppercase())
+        .chain(targets.iter().map(|target| target.to_uppercase()))
+        .collect();
+    target_names.sort();
+    target_names.dedup();
+    check_cfgs.extend(target_names.iter().map(|target| format!("LLVM_TARGET_{}", target)));
+    for &cfg in &[
+        "LLVM_ASSERTIONS",
+        "LLVM_RTTI",
+        "LLVM_DEBUG",
+        "LLVM_LINK_SHARED",
+        "LLVM_SYS_RUNTIME_LOADING",
+        "LLVM_SYS_BINDGEN",
+    ] {
+        check_cfgs.push(cfg.to_owned());
+    }
+    check_cfgs
+}
+
+/// Set a cfg for the crate, and remember it for the checks that evaluate the
+/// crate's `#[cfg]` attributes themselves.
+fn emit_cfg(cfgs: &mut Vec<String>, cfg: String) {
+    println!("cargo:rustc-cfg={}", cfg);
+    cfgs.push(cfg);
 }
 
 fn main() {
+    declare_cfgs();
+
     // First ensure that we have exactly one LLVM version selected
     let mut versions = vec![];
     if cfg!(feature = "llvm-8") {
@@ -256,6 +1050,12 @@ fn main() {
     if cfg!(feature = "llvm-10") {
         versions.push(Version::parse("10.0.0").unwrap());
     }
+    if cfg!(feature = "llvm-11") {
+        versions.push(Version::parse("11.0.0").unwrap());
+    }
+    if cfg!(feature = "llvm-15") {
+        versions.push(Version::parse("15.0.0").unwrap());
+    }
     let selected_version = if versions.len() == 0 {
         panic!("llvm-sys-featured: Please select an LLVM version using a Cargo feature.")
     } else if versions.len() > 1 {
@@ -266,79 +1066,296 @@ fn main() {
 
     // Behavior can be significantly affected by these vars.
     println!("cargo:rerun-if-env-changed={}", &*ENV_LLVM_PREFIX);
+    println!("cargo:rerun-if-env-changed={}", ENV_LLVM_CONFIG);
     println!("cargo:rerun-if-env-changed={}", &*ENV_STRICT_VERSIONING);
     println!("cargo:rerun-if-env-changed={}", &*ENV_NO_CLEAN_CFLAGS);
     println!("cargo:rerun-if-env-changed={}", &*ENV_USE_DEBUG_MSVCRT);
     println!("cargo:rerun-if-env-changed={}", &*ENV_FORCE_FFI);
+    println!("cargo:rerun-if-env-changed={}", ENV_PREFER_DYNAMIC);
+    println!("cargo:rerun-if-env-changed={}", ENV_LIBCPP);
+    println!("cargo:rerun-if-env-changed={}", ENV_TARGET_PREFIX);
+    println!("cargo:rerun-if-env-changed={}", ENV_TARGET_LLVM_CONFIG);
+    println!("cargo:rerun-if-env-changed={}", ENV_STUB);
+    println!("cargo:rerun-if-env-changed={}", ENV_SOURCE_DIR);
+    println!("cargo:rerun-if-env-changed={}", ENV_BUILD_CACHE);
 
-    let llvm_config_path = match get_llvm_config_path(selected_version) {
-        Some(path) => path,
+    // For convenience we set a number of configuration options to avoid
+    // checking complex combinations of features all the time:
+    // LLVM_VERSION_N_OR_GREATER and LLVM_VERSION_N_OR_LOWER for each major
+    // version N between the oldest and the newest version we support. Versions
+    // without their own feature (12 through 14) still get cfgs, so that
+    // bindings can be annotated with the release that actually changed them.
+    let mut cfgs = vec![];
+    for major in 9..=15 {
+        if selected_version.major >= major {
+            emit_cfg(&mut cfgs, format!("LLVM_VERSION_{}_OR_GREATER", major));
+        }
+    }
+    for major in 8..=14 {
+        if selected_version.major <= major {
+            emit_cfg(&mut cfgs, format!("LLVM_VERSION_{}_OR_LOWER", major));
+        }
+    }
+    // The `version` module reports the selected version, and the version
+    // of the LLVM we found, if any.
+    println!(
+        "cargo:rustc-env=LLVM_SYS_FEATURED_SELECTED_VERSION={}",
+        selected_version
+    );
+
+    // In stub mode there is no LLVM to look for, as the crate is only checked
+    // (by `cargo check`, clippy or an editor) and never linked. Every binding
+    // for the selected version is declared, as far as the `component-*`
+    // features allow, but nothing is built or linked.
+    //
+    // Runtime loading works the same way at build time, except that the
+    // bindings are shims that look up LLVM's functions when it is loaded.
+    let runtime_loading = cfg!(feature = "runtime-loading");
+    if runtime_loading || cfg!(feature = "stub") || env::var_os(ENV_STUB).is_some() {
+        let selected_components = get_selected_components();
+        let is_selected =
+            |name: &str| selected_components.is_empty() || selected_components.contains(&name);
+        for &(feature, _) in OPTIONAL_COMPONENTS {
+            if is_selected(feature) {
+                emit_cfg(&mut cfgs, format!("LLVM_COMPONENT_{}", feature.to_uppercase()));
+            }
+        }
+        for &target in TARGET_COMPONENTS {
+            if is_selected(target) {
+                emit_cfg(&mut cfgs, format!("LLVM_TARGET_{}", target.to_uppercase()));
+            }
+        }
+        if runtime_loading {
+            emit_cfg(&mut cfgs, "LLVM_SYS_RUNTIME_LOADING".to_owned());
+            write_runtime_symbols(
+                &get_active_cfgs(&cfgs),
+                Path::new(&env::var_os("OUT_DIR").unwrap()),
+            );
+        } else {
+            println!("cargo:rustc-cfg=LLVM_SYS_STUB");
+        }
+        export_cfgs(&cfgs, &[]);
+        return;
+    }
+
+    if is_cross_compiling()
+        && env::var_os(ENV_TARGET_PREFIX).is_none()
+        && env::var_os(ENV_TARGET_LLVM_CONFIG).is_none()
+    {
+        println!(
+            "cargo:warning=Cross-compiling, but neither {} nor {} is set; \
+             the LLVM libraries found may be for the build host rather than the target.",
+            ENV_TARGET_PREFIX, ENV_TARGET_LLVM_CONFIG
+        );
+    }
+
+    // Prefer llvm-config; some installations only have the CMake package.
+    let mut report = Report::default();
+    let found = match get_llvm_config_path(selected_version, &mut report) {
+        Some(path) => Some(LlvmConfig::Binary(path)),
+        None => cmake::find_package(selected_version, &mut report).map(LlvmConfig::CMake),
+    };
+    // As a last resort, build it from source.
+    #[cfg(feature = "vendored")]
+    let found = found
+        .or_else(|| vendored::build(selected_version, &mut report).map(LlvmConfig::Binary));
+    let llvm = match found {
+        Some(llvm) => llvm,
         None => {
+            report.print(true);
+            report.write_not_found(Path::new(&env::var_os("OUT_DIR").unwrap()));
             println!("cargo:rustc-cfg=LLVM_SYS_NOT_FOUND");
             return;
-        },
+        }
     };
+    // Bindgen panics if it can't load libclang, so look for it here, where it
+    // can go into the report and the same compile error.
+    #[cfg(feature = "bindgen")]
+    {
+        if !generate::find_libclang(&mut report) {
+            report.print(true);
+            report.write_failure(
+                Path::new(&env::var_os("OUT_DIR").unwrap()),
+                "The bindgen feature needs libclang, which was not found.",
+                "Set LIBCLANG_PATH to the directory containing libclang, install it \
+                 (e.g. the libclang-dev package), or disable the bindgen feature.",
+            );
+            println!("cargo:rustc-cfg=LLVM_SYS_NOT_FOUND");
+            return;
+        }
+    }
+    report.print(false);
 
-    // For convenience we set a number of configuration options to avoid
-    // checking complex combinations of features all the time.
-    // Is the LLVM version at least 9
-    if selected_version.major >= 9 {
-        println!("cargo:rustc-cfg=LLVM_VERSION_9_OR_GREATER");
+    // Components selected with `component-*` features restrict what we link,
+    // unless we link the shared library (which has everything) or don't link
+    // at all. Each available component other than a target backend gets an
+    // LLVM_COMPONENT_<NAME> cfg.
+    let link_kind = get_link_kind(&llvm);
+    let selected_components = get_selected_components();
+    let restrict_components = !selected_components.is_empty()
+        && link_kind == LinkKind::Static
+        && !cfg!(feature = "no-llvm-linking");
+    let mut components = vec![];
+    for &(feature, component) in OPTIONAL_COMPONENTS {
+        if !restrict_components || selected_components.contains(&feature) {
+            emit_cfg(&mut cfgs, format!("LLVM_COMPONENT_{}", feature.to_uppercase()));
+            components.push(component);
+        }
     }
-    // Is the LLVM version at least 10
-    if selected_version.major >= 10 {
-        println!("cargo:rustc-cfg=LLVM_VERSION_10_OR_GREATER");
+
+    // Likewise each target backend that this LLVM was built with, and that we
+    // link, gets an LLVM_TARGET_<NAME> cfg.
+    let targets_built = get_targets_built(&llvm);
+    let targets: Vec<String> = targets_built
+        .iter()
+        .map(|target| target.to_lowercase())
+        .filter(|target| !restrict_components || selected_components.contains(&&**target))
+        .collect();
+    for target in &targets {
+        emit_cfg(&mut cfgs, format!("LLVM_TARGET_{}", target.to_uppercase()));
     }
-    // Is the LLVM version at most 9
-    if selected_version.major <= 9 {
-        println!("cargo:rustc-cfg=LLVM_VERSION_9_OR_LOWER");
+    for feature in &selected_components {
+        if TARGET_COMPONENTS.contains(feature) && !targets.iter().any(|t| t == feature) {
+            panic!(
+                "llvm-sys-featured: The component-{} feature is enabled, but this LLVM was \
+                 built without that target.",
+                feature
+            );
+        }
     }
-    // Is the LLVM version at most 8
-    if selected_version.major <= 8 {
-        println!("cargo:rustc-cfg=LLVM_VERSION_8_OR_LOWER");
+    if restrict_components {
+        components.extend_from_slice(BASE_COMPONENTS);
+        components.extend(targets.iter().map(String::as_str));
+    } else {
+        // No components means all of them to llvm-config.
+        components.clear();
+    }
+
+    // Properties of the LLVM build get cfgs as well: LLVM_ASSERTIONS if it has
+    // assertions enabled, LLVM_RTTI if it was built with RTTI, LLVM_DEBUG for
+    // a debug build, and LLVM_LINK_SHARED if we link the shared library.
+    let build_config = get_llvm_build_config(&llvm);
+    if build_config.assertions {
+        emit_cfg(&mut cfgs, "LLVM_ASSERTIONS".to_owned());
+    }
+    if build_config.rtti {
+        emit_cfg(&mut cfgs, "LLVM_RTTI".to_owned());
+    }
+    if is_llvm_debug(&llvm) {
+        emit_cfg(&mut cfgs, "LLVM_DEBUG".to_owned());
+    }
+    if link_kind == LinkKind::Dynamic {
+        emit_cfg(&mut cfgs, "LLVM_LINK_SHARED".to_owned());
     }
 
     // Build the extra wrapper functions.
     if !cfg!(feature = "disable-alltargets-init") {
-        std::env::set_var("CFLAGS", get_llvm_cflags(&llvm_config_path));
-        cc::Build::new()
-            .file("wrappers/target.c")
-            .compile("targetwrappers");
+        std::env::set_var("CFLAGS", get_llvm_cflags(&llvm));
+        let mut build = cc::Build::new();
+        build.file("wrappers/target.c");
+        if restrict_components {
+            let out_dir = PathBuf::from(env::var_os("OUT_DIR").unwrap()).join("targets");
+            let has_native_target = write_target_lists(&llvm, &targets, &out_dir);
+            build.include(&out_dir).define("LLVM_SYS_FEATURED_TARGET_LISTS", None);
+            if !has_native_target {
+                build.define("LLVM_SYS_FEATURED_NO_NATIVE_TARGET", None);
+            }
+        }
+        build.compile("targetwrappers");
     }
 
-    if cfg!(feature = "no-llvm-linking") {
-        return;
+    // With the bindgen feature, lib.rs includes bindings generated from the
+    // headers instead of its own.
+    if cfg!(feature = "bindgen") {
+        emit_cfg(&mut cfgs, "LLVM_SYS_BINDGEN".to_owned());
     }
 
-    let libdir = llvm_config(&llvm_config_path, "--libdir");
-
-    // Export information to other crates
+    // Checks of our own sources against the LLVM headers evaluate their
+    // #[cfg]s with these. The check-bindings tool gets them at compile time.
+    let active_cfgs = get_active_cfgs(&cfgs);
+    println!("cargo:rustc-env=LLVM_SYS_FEATURED_CFGS={}", active_cfgs.join(" "));
+    println!(
+        "cargo:rustc-env=LLVM_SYS_FEATURED_LLVM_VERSION={}",
+        llvm_build_setting(&llvm, "--version").trim()
+    );
     println!(
-        "cargo:config_path={}",
-        llvm_config_path.display()
-    ); // will be DEP_LLVM_CONFIG_PATH
+        "cargo:rustc-env=LLVM_SYS_FEATURED_INCLUDEDIR={}",
+        get_includedir(&llvm).display()
+    );
+    #[cfg(feature = "bindgen")]
+    generate::generate(&llvm, &active_cfgs, Path::new(&env::var_os("OUT_DIR").unwrap()));
+    // Generated bindings match the headers already.
+    if cfg!(feature = "abi-probe") && !cfg!(feature = "bindgen") {
+        abi::probe(&llvm, &active_cfgs, Path::new(&env::var_os("OUT_DIR").unwrap()));
+    }
+
+    let libdir = get_libdir(&llvm);
+
+    // Export information to other crates, which their build scripts get as
+    // DEP_LLVM_<KEY>. Crates that compile their own code against LLVM should
+    // use these rather than look for LLVM again, and maybe find another one.
+    // The values are those llvm-config gives for the same options.
+    if let LlvmConfig::Binary(ref path) = llvm {
+        println!(
+            "cargo:config_path={}",
+            path.display()
+        ); // will be DEP_LLVM_CONFIG_PATH
+    }
     println!("cargo:libdir={}", libdir); // DEP_LLVM_LIBDIR
+    println!("cargo:includedir={}", get_includedir(&llvm).display()); // DEP_LLVM_INCLUDEDIR
+    println!("cargo:version={}", llvm_build_setting(&llvm, "--version").trim()); // DEP_LLVM_VERSION
+    println!(
+        "cargo:build_mode={}",
+        llvm_build_setting(&llvm, "--build-mode").trim()
+    ); // DEP_LLVM_BUILD_MODE
+    println!(
+        "cargo:assertion_mode={}",
+        if build_config.assertions { "ON" } else { "OFF" }
+    ); // DEP_LLVM_ASSERTION_MODE
+    println!("cargo:has_rtti={}", if build_config.rtti { "YES" } else { "NO" }); // DEP_LLVM_HAS_RTTI
+    println!(
+        "cargo:shared_mode={}",
+        match link_kind {
+            LinkKind::Static => "static",
+            LinkKind::Dynamic => "shared",
+        }
+    ); // DEP_LLVM_SHARED_MODE
+    println!("cargo:targets_built={}", targets_built.join(" ")); // DEP_LLVM_TARGETS_BUILT
+    export_cfgs(&cfgs, &targets_built);
+
+    if cfg!(feature = "no-llvm-linking") {
+        return;
+    }
 
     // Link LLVM libraries
     println!("cargo:rustc-link-search=native={}", libdir);
-    for name in get_link_libraries(&llvm_config_path) {
-        println!("cargo:rustc-link-lib=static={}", name);
+    for name in get_link_libraries(&llvm, link_kind, &components) {
+        println!("cargo:rustc-link-lib={}={}", link_kind.rustc_link_kind(), name);
     }
 
     // Link system libraries
-    for name in get_system_libraries(&llvm_config_path) {
+    let mut system_libraries = get_system_libraries(&llvm, link_kind, &components);
+    if link_kind == LinkKind::Static && !target_env_is("msvc") {
+        // The shared library already records its own dependencies, and MSVC
+        // builds name these libraries differently.
+        let missing = build_config.missing_system_libraries(&components, &system_libraries);
+        system_libraries.extend(missing.into_iter().map(str::to_owned));
+    }
+
+    // Link libffi if the user requested this workaround, even if LLVM
+    // doesn't seem to have been built with it.
+    // See https://bitbucket.org/tari/llvm-sys.rs/issues/12/
+    let force_ffi = env::var_os(&*ENV_FORCE_FFI).is_some();
+    if force_ffi && !system_libraries.iter().any(|name| name == "ffi") {
+        system_libraries.push("ffi".to_owned());
+    }
+
+    for name in system_libraries {
         println!("cargo:rustc-link-lib=dylib={}", name);
     }
 
     let use_debug_msvcrt = env::var_os(&*ENV_USE_DEBUG_MSVCRT).is_some();
-    if cfg!(target_env = "msvc") && (use_debug_msvcrt || is_llvm_debug(&llvm_config_path)) {
+    if target_env_is("msvc") && (use_debug_msvcrt || is_llvm_debug(&llvm)) {
         println!("cargo:rustc-link-lib={}", "msvcrtd");
     }
-
-    // Link libffi if the user requested this workaround.
-    // See https://bitbucket.org/tari/llvm-sys.rs/issues/12/
-    let force_ffi = env::var_os(&*ENV_FORCE_FFI).is_some();
-    if force_ffi {
-        println!("cargo:rustc-link-lib=dylib={}", "ffi");
-    }
 }
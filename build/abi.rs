@@ -0,0 +1,387 @@
+//! A check that the `#[repr(C)]` types in the bindings match the LLVM headers.
+//!
+//! The structs and enums in `src` are written by hand, so nothing stops them
+//! drifting from the C definitions, and a mismatch is undefined behavior
+//! rather than an error. With the `abi-probe` feature, we find every
+//! `#[repr(C)]` struct and enum whose `#[cfg]`s are satisfied, compile a C
+//! program against the real `llvm-c` headers that prints the size and field
+//! offsets of each struct and the value of each enumerator, and write the
+//! results to `OUT_DIR` as constant assertions. `lib.rs` includes them, so any
+//! disagreement fails the build with a message naming the type. The
+//! enumerators of each C enum are read from the headers too, so that one the
+//! Rust enum has no variant for fails the build as well.
+
+use std::collections::HashMap;
+use std::env;
+use std::fmt::Write as _;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use regex::Regex;
+
+use super::source::{attributes_enabled, find_modules, logical_lines};
+use super::{get_includedir, is_cross_compiling, LlvmConfig};
+
+/// The name of the file in `OUT_DIR` that `lib.rs` includes.
+static ASSERTIONS_FILE: &str = "abi_probe.rs";
+
+/// Enumerators whose names in the bindings differ from those in the headers.
+static C_NAMES: &[(&str, &str)] = &[
+    ("LLVMDWARFEmissionKindNone", "LLVMDWARFEmissionNone"),
+    ("LLVMDWARFEmissionKindFull", "LLVMDWARFEmissionFull"),
+    (
+        "LLVMDWARFEmissionKindLineTablesOnly",
+        "LLVMDWARFEmissionLineTablesOnly",
+    ),
+];
+
+/// The name in the headers of an enumerator in the bindings.
+fn c_name(variant: &str) -> &str {
+    C_NAMES
+        .iter()
+        .find(|&&(rust, _)| rust == variant)
+        .map_or(variant, |&(_, c)| c)
+}
+
+/// A `#[repr(C)]` struct or enum found in the bindings.
+#[derive(Debug)]
+struct Item {
+    /// The Rust path of the type, e.g. `crate::execution_engine::LLVMMCJITCompilerOptions`.
+    path: String,
+    /// The name of the type in Rust.
+    name: String,
+    is_enum: bool,
+    /// The fields of a struct or the variants of an enum.
+    members: Vec<String>,
+}
+
+/// One value to measure in C and compare in Rust.
+#[derive(Debug)]
+enum Check<'a> {
+    Size(&'a Item),
+    Offset(&'a Item, &'a str),
+    Value(&'a Item, &'a str),
+    /// A C enumerator with no variant of the same name, whose value must
+    /// still be that of one of the variants.
+    Covered(&'a Item, &'a str),
+}
+
+impl<'a> Check<'a> {
+    /// The C expression giving the value of this check.
+    fn c_expr(&self, c_types: &HashMap<&str, String>) -> String {
+        match *self {
+            Check::Size(item) => format!("sizeof({})", c_types[&*item.name]),
+            Check::Offset(item, field) => {
+                format!("offsetof({}, {})", c_types[&*item.name], field)
+            }
+            Check::Value(_, variant) => c_name(variant).to_owned(),
+            Check::Covered(_, enumerator) => enumerator.to_owned(),
+        }
+    }
+
+    /// A Rust constant assertion that this check has the value `expected`.
+    fn rust_assertion(&self, expected: i64) -> String {
+        if let Check::Covered(item, enumerator) = *self {
+            let variants: Vec<String> = item
+                .members
+                .iter()
+                .map(|variant| format!("{}::{} as i64 == {}", item.path, variant, expected))
+                .collect();
+            return format!(
+                "const _: () = assert!({}, \"{} ({}) in the LLVM headers has no variant in {}\");\n",
+                variants.join(" || "),
+                enumerator,
+                expected,
+                item.name
+            );
+        }
+        let (actual, description) = match *self {
+            Check::Size(item) => (
+                format!("::core::mem::size_of::<{}>() as i64", item.path),
+                format!("size of {}", item.name),
+            ),
+            Check::Offset(item, field) => (
+                format!("::core::mem::offset_of!({}, {}) as i64", item.path, field),
+                format!("offset of {}::{}", item.name, field),
+            ),
+            Check::Value(item, variant) => (
+                format!("{}::{} as i64", item.path, variant),
+                format!("value of {}::{}", item.name, variant),
+            ),
+            Check::Covered(..) => unreachable!(),
+        };
+        format!(
+            "const _: () = assert!({} == {}, \"the {} is {} in the LLVM headers\");\n",
+            actual, expected, description, expected
+        )
+    }
+}
+
+/// Compare the bindings against the LLVM headers, writing the assertions for
+/// `lib.rs` to `out_dir`. `cfgs` are the active cfgs, as described in
+/// `source.rs`.
+pub fn probe(llvm: &LlvmConfig, cfgs: &[String], out_dir: &Path) {
+    let manifest_dir = PathBuf::from(env::var_os("CARGO_MANIFEST_DIR").unwrap());
+    let src_dir = manifest_dir.join("src");
+    println!("cargo:rerun-if-changed={}", src_dir.display());
+
+    // The probe has to run on the build host.
+    if is_cross_compiling() {
+        println!("cargo:warning=Cross-compiling, so the abi-probe feature has no effect.");
+        write_assertions(out_dir, "");
+        return;
+    }
+
+    let items = find_items(&src_dir, cfgs);
+    let includedir = get_includedir(llvm);
+    let headers = find_headers(&includedir);
+    let header_text: String = headers
+        .iter()
+        .map(|header| fs::read_to_string(header).unwrap_or_default())
+        .collect();
+
+    // Most of these types are typedefs, but some only have a struct tag in C.
+    let c_types: HashMap<&str, String> = items
+        .iter()
+        .map(|item| {
+            let c_name = item.name.trim_start_matches("Struct_");
+            let keyword = if item.is_enum { "enum" } else { "struct" };
+            let tag = Regex::new(&format!(r"\b{}\s+{}\s*\{{", keyword, c_name)).unwrap();
+            let c_type = if tag.is_match(&header_text) {
+                format!("{} {}", keyword, c_name)
+            } else {
+                c_name.to_owned()
+            };
+            (&*item.name, c_type)
+        })
+        .collect();
+
+    let c_enums = find_c_enums(&header_text);
+    let mut checks = vec![];
+    for item in &items {
+        checks.push(Check::Size(item));
+        for member in &item.members {
+            checks.push(if item.is_enum {
+                Check::Value(item, member)
+            } else {
+                Check::Offset(item, member)
+            });
+        }
+        if item.is_enum {
+            checks.extend(
+                unnamed_enumerators(item, &c_enums)
+                    .into_iter()
+                    .map(|enumerator| Check::Covered(item, enumerator)),
+            );
+        }
+    }
+
+    let mut program = String::from("#include <stddef.h>\n#include <stdio.h>\n");
+    for header in &headers {
+        writeln!(program, "#include \"{}\"", header.display()).unwrap();
+    }
+    program.push_str("\nint main(void) {\n");
+    for check in &checks {
+        writeln!(
+            program,
+            "    printf(\"%lld\\n\", (long long)({}));",
+            check.c_expr(&c_types)
+        )
+        .unwrap();
+    }
+    program.push_str("    return 0;\n}\n");
+
+    let output = run_program(&program, &includedir, &out_dir.join("abi_probe"));
+    let values: Vec<i64> = output
+        .lines()
+        .map(|line| {
+            line.trim()
+                .parse()
+                .expect("Unexpected output from the ABI probe")
+        })
+        .collect();
+    assert_eq!(
+        values.len(),
+        checks.len(),
+        "Unexpected output from the ABI probe"
+    );
+
+    let mut assertions =
+        String::from("// Generated by the build script from the LLVM headers; see build/abi.rs.\n");
+    for (check, &value) in checks.iter().zip(&values) {
+        assertions.push_str(&check.rust_assertion(value));
+    }
+    write_assertions(out_dir, &assertions);
+}
+
+fn write_assertions(out_dir: &Path, assertions: &str) {
+    fs::write(out_dir.join(ASSERTIONS_FILE), assertions)
+        .expect("Failed to write the ABI probe results");
+}
+
+/// Compile and run a C program, returning its output.
+fn run_program(source: &str, includedir: &Path, out_dir: &Path) -> String {
+    fs::create_dir_all(out_dir).expect("Failed to create the ABI probe directory");
+    let source_path = out_dir.join("probe.c");
+    let exe_path = out_dir.join(if cfg!(windows) { "probe.exe" } else { "probe" });
+    fs::write(&source_path, source).expect("Failed to write the ABI probe");
+
+    let compiler = cc::Build::new()
+        .cargo_metadata(false)
+        .include(includedir)
+        .get_compiler();
+    let mut command = compiler.to_command();
+    if compiler.is_like_msvc() {
+        command
+            .arg(&source_path)
+            .arg(format!("/Fo{}\\", out_dir.display()))
+            .arg(format!("/Fe{}", exe_path.display()));
+    } else {
+        command.arg(&source_path).arg("-o").arg(&exe_path);
+    }
+    let compiled = command
+        .output()
+        .expect("Failed to run the C compiler for the ABI probe");
+    if !compiled.status.success() {
+        panic!(
+            "llvm-sys-featured: The ABI probe didn't compile, which usually means that a binding \
+             refers to a type or enumerator that doesn't exist in this version of LLVM:\n{}{}",
+            String::from_utf8_lossy(&compiled.stdout),
+            String::from_utf8_lossy(&compiled.stderr)
+        );
+    }
+
+    let ran = Command::new(&exe_path)
+        .output()
+        .expect("Failed to run the ABI probe");
+    if !ran.status.success() {
+        panic!("llvm-sys-featured: The ABI probe failed: {:?}", ran);
+    }
+    String::from_utf8(ran.stdout).expect("Unexpected output from the ABI probe")
+}
+
+/// Find the `llvm-c` headers in `includedir`.
+pub fn find_headers(includedir: &Path) -> Vec<PathBuf> {
+    let mut headers = vec![];
+    for dir in &["llvm-c", "llvm-c/Transforms"] {
+        if let Ok(entries) = fs::read_dir(includedir.join(dir)) {
+            headers.extend(
+                entries
+                    .filter_map(|entry| entry.ok())
+                    .map(|entry| entry.path())
+                    .filter(|path| path.extension().and_then(|ext| ext.to_str()) == Some("h")),
+            );
+        }
+    }
+    headers.sort();
+    headers
+}
+
+/// Find the enumerators of every enum in the headers, named or not.
+fn find_c_enums(header_text: &str) -> Vec<Vec<String>> {
+    let comment = Regex::new(r"(?s)/\*.*?\*/|//[^\n]*").unwrap();
+    let body = Regex::new(r"\benum\b[^{};()]*\{(?P<body>[^}]*)\}").unwrap();
+    let enumerator = Regex::new(r"^\s*(\w+)").unwrap();
+    let text = comment.replace_all(header_text, "");
+    body.captures_iter(&text)
+        .map(|c| {
+            c["body"]
+                .lines()
+                .filter(|line| !line.trim_start().starts_with('#'))
+                .collect::<Vec<_>>()
+                .join("\n")
+                .split(',')
+                .filter_map(|e| enumerator.captures(e).map(|e| e[1].to_owned()))
+                .collect()
+        })
+        .collect()
+}
+
+/// The enumerators of the C enum matching `item` (the one with its first
+/// variant) that `item` has no variant for.
+fn unnamed_enumerators<'a>(item: &Item, c_enums: &'a [Vec<String>]) -> Vec<&'a str> {
+    let named: Vec<&str> = item.members.iter().map(|m| c_name(m)).collect();
+    let first = match named.first() {
+        Some(first) => *first,
+        None => return vec![],
+    };
+    match c_enums.iter().find(|c_enum| c_enum.iter().any(|e| e == first)) {
+        Some(c_enum) => c_enum
+            .iter()
+            .map(String::as_str)
+            .filter(|e| !named.contains(e))
+            .collect(),
+        None => vec![],
+    }
+}
+
+/// Find the `#[repr(C)]` types in the modules of the crate that are enabled
+/// by `cfgs`.
+fn find_items(src_dir: &Path, cfgs: &[String]) -> Vec<Item> {
+    let mut items = vec![];
+    for (path, file) in find_modules(src_dir, cfgs) {
+        let source = fs::read_to_string(&file)
+            .unwrap_or_else(|e| panic!("Failed to read {}: {}", file.display(), e));
+        items.extend(parse_items(&source, &path, cfgs));
+    }
+    items
+}
+
+/// Parse the top-level `#[repr(C)]` structs and enums in `source`, keeping
+/// only the items, fields and variants enabled by `cfgs`.
+fn parse_items(source: &str, module: &str, cfgs: &[String]) -> Vec<Item> {
+    let declaration = Regex::new(r"^pub (struct|enum) (\w+)\s*\{$").unwrap();
+    let member = Regex::new(r"^(?:pub )?(\w+)\s*(?::|=|,|$)").unwrap();
+
+    let mut items = vec![];
+    let logical_lines = logical_lines(source);
+    let mut lines = logical_lines.iter().map(String::as_str);
+    let mut attributes = vec![];
+    while let Some(line) = lines.next() {
+        let trimmed = line.trim();
+        if trimmed.starts_with("#[") || trimmed.starts_with("//") {
+            attributes.push(trimmed.to_owned());
+            continue;
+        }
+        let captures = match declaration.captures(line) {
+            Some(captures) => captures,
+            None => {
+                attributes.clear();
+                continue;
+            }
+        };
+        let is_repr_c = attributes.iter().any(|a| a == "#[repr(C)]");
+        let enabled = attributes_enabled(&attributes, cfgs);
+        attributes.clear();
+
+        let mut members = vec![];
+        for line in lines.by_ref() {
+            if line == "}" {
+                break;
+            }
+            let trimmed = line.trim();
+            if trimmed.starts_with("#[") || trimmed.starts_with("//") {
+                attributes.push(trimmed.to_owned());
+                continue;
+            }
+            if let Some(member) = member.captures(trimmed) {
+                if attributes_enabled(&attributes, cfgs) {
+                    members.push(member[1].to_owned());
+                }
+            }
+            attributes.clear();
+        }
+        attributes.clear();
+
+        if is_repr_c && enabled {
+            items.push(Item {
+                path: format!("{}::{}", module, &captures[2]),
+                name: captures[2].to_owned(),
+                is_enum: &captures[1] == "enum",
+                members,
+            });
+        }
+    }
+    items
+}
@@ -0,0 +1,456 @@
+//! Discovery of LLVM through its CMake package (`lib/cmake/llvm/LLVMConfig.cmake`),
+//! for installations that don't ship `llvm-config`.
+//!
+//! `LlvmPackage::query` answers the subset of `llvm-config` queries that the
+//! build script makes, in the same format, so that everything else works the
+//! same way whichever of the two we found.
+
+use super::report::Report;
+use super::{
+    is_compatible_llvm, target_env_is, target_os_is, version_requirement, LinkKind, ENV_LLVM_PREFIX,
+};
+use regex::Regex;
+use semver::Version;
+use std::collections::{HashMap, HashSet};
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// An LLVM installation described by its CMake package.
+#[derive(Debug)]
+pub struct LlvmPackage {
+    /// The directory containing LLVMConfig.cmake
+    cmakedir: PathBuf,
+    /// Variables set in LLVMConfig.cmake, with `${...}` references to earlier
+    /// ones expanded
+    variables: HashMap<String, String>,
+    /// Library targets from LLVMExports.cmake, by target name
+    libraries: HashMap<String, Library>,
+    /// Names of the static LLVM component libraries, in the order they are
+    /// exported
+    components: Vec<String>,
+}
+
+/// A library target exported by LLVM's CMake package.
+#[derive(Debug, Default)]
+struct Library {
+    /// IMPORTED_LOCATION: the library file
+    location: Option<PathBuf>,
+    /// INTERFACE_LINK_LIBRARIES: other targets, system library names or paths
+    link_libraries: Vec<String>,
+}
+
+/// Search for LLVM's CMake package under `LLVM_SYS_FEATURED_PREFIX`, or under
+/// a few usual prefixes if that isn't set, and return the first one with a
+/// compatible version. Every package tried is recorded in `report`.
+pub fn find_package(selected_version: &Version, report: &mut Report) -> Option<LlvmPackage> {
+    let prefixes: Vec<PathBuf> = match env::var_os(ENV_LLVM_PREFIX) {
+        Some(prefix) => vec![prefix.into()],
+        None => vec![
+            format!("/usr/lib/llvm-{}", selected_version.major).into(),
+            "/usr/local".into(),
+            "/usr".into(),
+        ],
+    };
+
+    for prefix in &prefixes {
+        for libdir in &["lib", "lib64"] {
+            let cmakedir = prefix.join(libdir).join("cmake").join("llvm");
+            if !cmakedir.join("LLVMConfig.cmake").is_file() {
+                continue;
+            }
+            let package = LlvmPackage::read(&cmakedir);
+            match package.version() {
+                Some(ref version) if is_compatible_llvm(version, selected_version) => {
+                    report.add(format!(
+                        "CMake package in {}: LLVM {}, used",
+                        cmakedir.display(),
+                        version
+                    ));
+                    return Some(package);
+                }
+                Some(version) => report.add(format!(
+                    "CMake package in {}: LLVM {}, rejected (need {})",
+                    cmakedir.display(),
+                    version,
+                    version_requirement(selected_version)
+                )),
+                None => report.add(format!(
+                    "CMake package in {}: could not determine the LLVM version",
+                    cmakedir.display()
+                )),
+            }
+        }
+    }
+    report.add(format!(
+        "no compatible LLVM CMake package under {}",
+        prefixes
+            .iter()
+            .map(|p| p.display().to_string())
+            .collect::<Vec<_>>()
+            .join(", ")
+    ));
+    None
+}
+
+/// Read the package in `cmakedir`, if there is one there.
+pub fn read_package(cmakedir: &Path) -> Option<LlvmPackage> {
+    if cmakedir.join("LLVMConfig.cmake").is_file() {
+        Some(LlvmPackage::read(cmakedir))
+    } else {
+        None
+    }
+}
+
+/// Read the variables set in a CMake file with plain `set(NAME VALUE)`
+/// commands, which is how LLVMConfig.cmake records its build settings.
+///
+/// `${NAME}` references to variables set earlier in the file, or given in
+/// `predefined`, are expanded; anything that needs CMake to evaluate is left
+/// out.
+pub fn read_variables(
+    path: &Path,
+    predefined: HashMap<String, String>,
+) -> Option<HashMap<String, String>> {
+    let contents = fs::read_to_string(path).ok()?;
+    let set = Regex::new(r#"(?m)^\s*set\((?P<name>\w+) "?(?P<value>[^")]*)"?\)\s*$"#).unwrap();
+    let mut variables = predefined;
+    for c in set.captures_iter(&contents) {
+        let value = expand(&c["value"], &variables);
+        variables.insert(c["name"].to_owned(), value);
+    }
+    Some(variables)
+}
+
+/// Expand `${NAME}` references to the given variables; unknown variables
+/// expand to nothing, as in CMake.
+fn expand(value: &str, variables: &HashMap<String, String>) -> String {
+    let reference = Regex::new(r"\$\{(?P<name>\w+)\}").unwrap();
+    reference
+        .replace_all(value, |c: &regex::Captures| {
+            variables.get(&c["name"]).cloned().unwrap_or_default()
+        })
+        .into_owned()
+}
+
+/// Split a CMake list.
+fn list(value: &str) -> impl Iterator<Item = &str> {
+    value.split(';').filter(|item| !item.is_empty())
+}
+
+impl LlvmPackage {
+    /// Read the package in the given directory.
+    fn read(cmakedir: &Path) -> LlvmPackage {
+        // LLVMConfig.cmake computes the install prefix as four levels up from
+        // its own real path (prefix/lib/cmake/llvm/LLVMConfig.cmake).
+        let config_file = cmakedir.join("LLVMConfig.cmake");
+        let prefix = fs::canonicalize(&config_file)
+            .unwrap_or_else(|_| config_file.clone())
+            .ancestors()
+            .nth(4)
+            .map(Path::to_path_buf)
+            .unwrap_or_default();
+        let mut predefined = HashMap::new();
+        predefined.insert(
+            "LLVM_INSTALL_PREFIX".to_owned(),
+            prefix.display().to_string(),
+        );
+        let variables = read_variables(&config_file, predefined)
+            .unwrap_or_else(|| panic!("Failed to read {}", config_file.display()));
+
+        // LLVMExports.cmake declares the targets and their dependencies, and
+        // one LLVMExports-<config>.cmake per build configuration their
+        // locations.
+        let mut import_variables = HashMap::new();
+        import_variables.insert("_IMPORT_PREFIX".to_owned(), prefix.display().to_string());
+        let add_library =
+            Regex::new(r"(?m)^add_library\((?P<name>[\w-]+) (?P<kind>STATIC|SHARED) IMPORTED\)")
+                .unwrap();
+        let properties = Regex::new(
+            r"(?s)set_target_properties\((?P<name>[\w-]+) PROPERTIES\n(?P<props>.*?)\n\s*\)",
+        )
+        .unwrap();
+        let property = Regex::new(r#"(?m)^\s*(?P<key>\w+) "(?P<value>[^"]*)""#).unwrap();
+
+        let mut libraries: HashMap<String, Library> = HashMap::new();
+        let mut components = vec![];
+        let mut export_files = vec![cmakedir.join("LLVMExports.cmake")];
+        if let Ok(entries) = fs::read_dir(cmakedir) {
+            export_files.extend(
+                entries
+                    .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+                    .filter(|path| {
+                        path.file_name()
+                            .and_then(|name| name.to_str())
+                            .unwrap_or_default()
+                            .starts_with("LLVMExports-")
+                    }),
+            );
+        }
+        for export_file in export_files {
+            let exports = match fs::read_to_string(&export_file) {
+                Ok(exports) => exports,
+                Err(_) => continue,
+            };
+            for c in add_library.captures_iter(&exports) {
+                libraries.entry(c["name"].to_owned()).or_default();
+                if &c["kind"] == "STATIC" && c["name"].starts_with("LLVM") {
+                    components.push(c["name"].to_owned());
+                }
+            }
+            for c in properties.captures_iter(&exports) {
+                let library = match libraries.get_mut(&c["name"]) {
+                    Some(library) => library,
+                    None => continue,
+                };
+                for p in property.captures_iter(&c["props"]) {
+                    let value = expand(&p["value"], &import_variables);
+                    if p["key"].starts_with("IMPORTED_LOCATION") {
+                        library.location = Some(value.into());
+                    } else if &p["key"] == "INTERFACE_LINK_LIBRARIES" {
+                        library.link_libraries = list(&value).map(link_only).collect();
+                    }
+                }
+            }
+        }
+
+        // Component libraries without a file (e.g. from a partial install)
+        // can't be linked.
+        components.retain(
+            |name| matches!(&libraries[name].location, Some(location) if location.is_file()),
+        );
+
+        LlvmPackage {
+            cmakedir: cmakedir.to_path_buf(),
+            variables,
+            libraries,
+            components,
+        }
+    }
+
+    /// Get a variable from LLVMConfig.cmake, or the empty string.
+    fn variable(&self, name: &str) -> &str {
+        self.variables.get(name).map_or("", String::as_str)
+    }
+
+    /// The LLVM version of this package.
+    fn version(&self) -> Option<Version> {
+        let version = self.variable("LLVM_PACKAGE_VERSION");
+        // Same as for llvm-config --version: strip suffixes like `git`.
+        let re = Regex::new(r"^\d+\.\d+(\.\d+)?").unwrap();
+        let numeric = re.find(version)?.as_str();
+        let numeric = if numeric.matches('.').count() == 1 {
+            format!("{}.0", numeric)
+        } else {
+            numeric.to_owned()
+        };
+        Version::parse(&numeric).ok()
+    }
+
+    /// The path to the shared library, if there is one.
+    fn shared_library(&self) -> Option<&Path> {
+        self.libraries
+            .get("LLVM")
+            .and_then(|library| library.location.as_deref())
+            .filter(|location| location.is_file())
+    }
+
+    /// Answer an `llvm-config` query, in the format `llvm-config` would use.
+    ///
+    /// Only the queries the build script makes are supported.
+    pub fn query(&self, args: &[&str], link_kind: LinkKind) -> String {
+        let (query, components) = args.split_first().expect("Empty llvm-config query");
+        let prefix = self.variable("LLVM_INSTALL_PREFIX");
+        match *query {
+            "--version" => self.variable("LLVM_PACKAGE_VERSION").to_owned(),
+            "--prefix" => prefix.to_owned(),
+            "--libdir" => self.variable("LLVM_LIBRARY_DIR").to_owned(),
+            "--includedir" => self.variable("LLVM_INCLUDE_DIRS").to_owned(),
+            "--cmakedir" => self.cmakedir.display().to_string(),
+            "--build-mode" => self.variable("LLVM_BUILD_TYPE").to_owned(),
+            "--targets-built" => list(self.variable("LLVM_TARGETS_TO_BUILD"))
+                .collect::<Vec<_>>()
+                .join(" "),
+            "--has-rtti" => yes_no(self.variable("LLVM_ENABLE_RTTI"), "YES", "NO"),
+            "--assertion-mode" => yes_no(self.variable("LLVM_ENABLE_ASSERTIONS"), "ON", "OFF"),
+            "--shared-mode" => match (link_kind, self.shared_library()) {
+                (LinkKind::Dynamic, Some(_)) => "shared".to_owned(),
+                _ => "static".to_owned(),
+            },
+            "--cflags" | "--cxxflags" => {
+                let mut flags = vec![format!("-I{}", self.variable("LLVM_INCLUDE_DIRS"))];
+                if *query == "--cxxflags" {
+                    flags.push("-std=c++14".to_owned());
+                }
+                if target_os_is("linux") {
+                    flags.push("-D_GNU_SOURCE".to_owned());
+                }
+                flags.extend(
+                    [
+                        "__STDC_CONSTANT_MACROS",
+                        "__STDC_FORMAT_MACROS",
+                        "__STDC_LIMIT_MACROS",
+                    ]
+                    .iter()
+                    .map(|define| format!("-D{}", define)),
+                );
+                flags.join(" ")
+            }
+            "--libnames" => match link_kind {
+                LinkKind::Dynamic => self
+                    .shared_library()
+                    .and_then(Path::file_name)
+                    .map(|name| name.to_string_lossy().into_owned())
+                    .unwrap_or_default(),
+                LinkKind::Static => self
+                    .resolve(components)
+                    .iter()
+                    .filter_map(|name| self.libraries[name].location.as_ref())
+                    .filter_map(|location| location.file_name())
+                    .map(|name| name.to_string_lossy().into_owned())
+                    .collect::<Vec<_>>()
+                    .join(" "),
+            },
+            // Like llvm-config, report the system libraries of LLVMSupport,
+            // which every component depends on. The few that only other
+            // components need (libffi, libxml2) are taken care of along with
+            // the other build settings. The shared library records its own.
+            "--system-libs" => match (link_kind, self.libraries.get("LLVMSupport")) {
+                (LinkKind::Static, Some(support)) => support
+                    .link_libraries
+                    .iter()
+                    .filter(|item| !self.libraries.contains_key(*item))
+                    .filter_map(|item| system_library_flag(item))
+                    .collect::<Vec<_>>()
+                    .join(" "),
+                _ => String::new(),
+            },
+            _ => panic!(
+                "llvm-config {} is not available for LLVM found through its CMake package in {}",
+                query,
+                self.cmakedir.display()
+            ),
+        }
+    }
+
+    /// Get the component libraries needed for the given llvm-config
+    /// components (or all of them, if `components` is empty), including
+    /// their dependencies, ordered so that each library comes before the
+    /// ones it depends on.
+    fn resolve(&self, components: &[&str]) -> Vec<String> {
+        let roots: Vec<&str> = if components.is_empty() {
+            self.components.iter().map(String::as_str).collect()
+        } else {
+            let targets: Vec<&str> = list(self.variable("LLVM_TARGETS_TO_BUILD")).collect();
+            components
+                .iter()
+                .flat_map(|component| self.component_libraries(component, &targets))
+                .collect()
+        };
+
+        // Depth-first post-order gives dependencies before dependents; we
+        // want the reverse of that.
+        fn visit<'a>(
+            package: &'a LlvmPackage,
+            name: &'a str,
+            visited: &mut HashSet<&'a str>,
+            order: &mut Vec<String>,
+        ) {
+            if !visited.insert(name) {
+                return;
+            }
+            for dependency in &package.libraries[name].link_libraries {
+                if package.components.contains(dependency) {
+                    visit(package, dependency, visited, order);
+                }
+            }
+            order.push(name.to_owned());
+        }
+        let mut visited = HashSet::new();
+        let mut order = vec![];
+        for root in roots {
+            visit(self, root, &mut visited, &mut order);
+        }
+        order.reverse();
+        order
+    }
+
+    /// Get the component libraries making up one llvm-config component: for
+    /// a target, e.g. `x86`, all of LLVMX86CodeGen, LLVMX86Desc, ...; for
+    /// anything else the one library with the same name, e.g. `LLVMMCJIT`
+    /// for `mcjit`.
+    fn component_libraries<'a>(&'a self, component: &str, targets: &[&str]) -> Vec<&'a str> {
+        let libraries: Vec<&str> = match targets
+            .iter()
+            .find(|target| target.eq_ignore_ascii_case(component))
+        {
+            Some(target) => {
+                let prefix = format!("LLVM{}", target);
+                self.components
+                    .iter()
+                    .filter(|name| {
+                        name.starts_with(&prefix)
+                            && name[prefix.len()..].starts_with(char::is_uppercase)
+                    })
+                    .map(String::as_str)
+                    .collect()
+            }
+            None => self
+                .components
+                .iter()
+                .filter(|name| name[4..].eq_ignore_ascii_case(component))
+                .map(String::as_str)
+                .collect(),
+        };
+        if libraries.is_empty() {
+            panic!(
+                "LLVM component {} is not available in the CMake package in {}",
+                component,
+                self.cmakedir.display()
+            );
+        }
+        libraries
+    }
+}
+
+/// Unwrap `$<LINK_ONLY:...>`, which only says that a dependency isn't needed
+/// for compiling, from an entry of INTERFACE_LINK_LIBRARIES.
+fn link_only(item: &str) -> String {
+    let item = item.trim_start_matches('\\');
+    if item.starts_with("$<LINK_ONLY:") && item.ends_with('>') {
+        item["$<LINK_ONLY:".len()..item.len() - 1].to_owned()
+    } else {
+        item.to_owned()
+    }
+}
+
+/// Turn a CMake boolean into one of two llvm-config style answers.
+fn yes_no(value: &str, yes: &str, no: &str) -> String {
+    if super::is_cmake_true(value) { yes } else { no }.to_owned()
+}
+
+/// Turn an entry of INTERFACE_LINK_LIBRARIES that isn't an LLVM library into
+/// a linker flag, as `llvm-config --system-libs` would print it.
+fn system_library_flag(item: &str) -> Option<String> {
+    let name = match item {
+        // Generator expressions need CMake to evaluate them
+        _ if item.starts_with("$<") => return None,
+        // Paths are passed along as they are
+        _ if Path::new(item).is_absolute() => return Some(item.to_owned()),
+        // Imported targets from the Find* modules LLVM uses
+        "ZLIB::ZLIB" => "z",
+        "FFI::ffi" => "ffi",
+        "Terminfo::terminfo" => "tinfo",
+        "LibXml2::LibXml2" => "xml2",
+        "Threads::Threads" if !target_env_is("msvc") => "pthread",
+        _ if item.contains("::") => return None,
+        _ if item.starts_with("-l") => &item[2..],
+        _ if item.starts_with('-') => return None,
+        _ => item.trim_end_matches(".lib"),
+    };
+    Some(if target_env_is("msvc") {
+        format!("{}.lib", name)
+    } else {
+        format!("-l{}", name)
+    })
+}
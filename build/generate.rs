@@ -0,0 +1,302 @@
+//! Generating the bindings from the LLVM headers with bindgen, for the
+//! `bindgen` feature.
+//!
+//! The generated code replaces everything in `lib.rs`, so it is arranged the
+//! same way: the types from `Types.h` and `Core.h` at the crate root, a
+//! `prelude` with the `LLVMBool` and `*Ref` aliases, and one module for each
+//! header, named as in `lib.rs`. Every module imports the root and all of the
+//! other modules, since the headers use each other's types freely. Modules
+//! for components that aren't enabled are left out, as in `lib.rs`.
+
+use std::fs;
+use std::path::Path;
+
+use regex::Regex;
+
+use super::abi::find_headers;
+use super::report::Report;
+use super::source::attributes_enabled;
+use super::{get_includedir, get_llvm_cflags, LlvmConfig};
+
+/// The name of the file in `OUT_DIR` that `lib.rs` includes.
+static BINDINGS_FILE: &str = "bindings.rs";
+
+/// The module for each header (relative to `llvm-c`), and the cfg enabling it
+/// if it has one. Headers that aren't listed get a module named after them.
+static MODULES: &[(&str, &str, Option<&str>)] = &[
+    ("Analysis.h", "analysis", None),
+    ("BitReader.h", "bit_reader", None),
+    ("BitWriter.h", "bit_writer", None),
+    ("Comdat.h", "comdat", None),
+    ("Core.h", "core", None),
+    ("DebugInfo.h", "debuginfo", None),
+    ("Disassembler.h", "disassembler", None),
+    ("DisassemblerTypes.h", "disassembler", None),
+    ("Error.h", "error", None),
+    ("ErrorHandling.h", "error_handling", None),
+    (
+        "ExecutionEngine.h",
+        "execution_engine",
+        Some("any(LLVM_COMPONENT_MCJIT, LLVM_COMPONENT_INTERPRETER)"),
+    ),
+    ("IRReader.h", "ir_reader", None),
+    ("Initialization.h", "initialization", None),
+    (
+        "LinkTimeOptimizer.h",
+        "link_time_optimizer",
+        Some("LLVM_COMPONENT_LTO"),
+    ),
+    ("Linker.h", "linker", None),
+    ("lto.h", "lto", Some("LLVM_COMPONENT_LTO")),
+    ("Object.h", "object", None),
+    ("OrcBindings.h", "orc", Some("LLVM_COMPONENT_ORC")),
+    ("Orc.h", "orc", Some("LLVM_COMPONENT_ORC")),
+    ("OrcEE.h", "orc", Some("LLVM_COMPONENT_ORC")),
+    ("LLJIT.h", "orc", Some("LLVM_COMPONENT_ORC")),
+    ("OptRemarks.h", "opt_remarks", None),
+    ("Remarks.h", "remarks", None),
+    ("Support.h", "support", None),
+    ("Target.h", "target", None),
+    ("TargetMachine.h", "target_machine", None),
+    (
+        "Transforms/AggressiveInstCombine.h",
+        "transforms::aggressive_instcombine",
+        None,
+    ),
+    (
+        "Transforms/Coroutines.h",
+        "transforms::coroutines",
+        Some("LLVM_COMPONENT_COROUTINES"),
+    ),
+    ("Transforms/InstCombine.h", "transforms::instcombine", None),
+    (
+        "Transforms/IPO.h",
+        "transforms::ipo",
+        Some("LLVM_COMPONENT_IPO"),
+    ),
+    (
+        "Transforms/PassManagerBuilder.h",
+        "transforms::pass_manager_builder",
+        Some("LLVM_COMPONENT_IPO"),
+    ),
+    ("Transforms/Scalar.h", "transforms::scalar", None),
+    ("Transforms/Utils.h", "transforms::util", None),
+    ("Transforms/Vectorize.h", "transforms::vectorize", None),
+];
+
+/// Headers that only hold definitions for the other headers to use.
+static HELPER_HEADERS: &[&str] = &["DataTypes.h", "Deprecated.h", "ExternC.h", "Visibility.h"];
+
+/// Our functions from `wrappers/target.c`, which `target.rs` also declares.
+static TARGET_WRAPPERS: &str = "
+extern \"C\" {
+    pub fn LLVM_InitializeAllTargetInfos();
+    pub fn LLVM_InitializeAllTargets();
+    pub fn LLVM_InitializeAllTargetMCs();
+    pub fn LLVM_InitializeAllAsmPrinters();
+    pub fn LLVM_InitializeAllAsmParsers();
+    pub fn LLVM_InitializeAllDisassemblers();
+
+    /// Returns 1 on failure.
+    pub fn LLVM_InitializeNativeTarget() -> LLVMBool;
+    /// Returns 1 on failure.
+    pub fn LLVM_InitializeNativeAsmParser() -> LLVMBool;
+    /// Returns 1 on failure.
+    pub fn LLVM_InitializeNativeAsmPrinter() -> LLVMBool;
+    /// Returns 1 on failure.
+    pub fn LLVM_InitializeNativeDisassembler() -> LLVMBool;
+}
+";
+
+/// Load libclang, which bindgen needs, recording in `report` which one was
+/// found or why none was. Returns whether it was found; bindgen would panic
+/// without it.
+pub fn find_libclang(report: &mut Report) -> bool {
+    match clang_sys::load() {
+        Ok(()) => {
+            if let Some(library) = clang_sys::get_library() {
+                report.add(format!("{}: libclang, used for bindgen", library.path().display()));
+            }
+            true
+        }
+        Err(e) => {
+            report.add(format!("libclang (needed by the bindgen feature): {}", e));
+            false
+        }
+    }
+}
+
+/// Generate the bindings from the headers of `llvm`, writing them to
+/// `out_dir`. `cfgs` are the active cfgs, as described in `source.rs`.
+pub fn generate(llvm: &LlvmConfig, cfgs: &[String], out_dir: &Path) {
+    let includedir = get_includedir(llvm);
+    let clang_args: Vec<String> = get_llvm_cflags(llvm)
+        .split_whitespace()
+        .map(str::to_owned)
+        .collect();
+
+    // Group the headers by module, in the order of their names.
+    let mut modules: Vec<(String, Vec<String>)> = vec![];
+    for header in find_headers(&includedir) {
+        let name = header
+            .strip_prefix(includedir.join("llvm-c"))
+            .unwrap()
+            .to_string_lossy()
+            .replace('\\', "/");
+        if name == "Types.h" || HELPER_HEADERS.contains(&&*name) {
+            continue;
+        }
+        let module = match MODULES.iter().find(|&&(header, _, _)| header == name) {
+            Some(&(_, module, cfg)) => {
+                if let Some(cfg) = cfg {
+                    if !attributes_enabled(&[format!("#[cfg({})]", cfg)], cfgs) {
+                        continue;
+                    }
+                }
+                module.to_owned()
+            }
+            None => module_name(&name),
+        };
+        match modules.iter_mut().find(|(m, _)| *m == module) {
+            Some((_, headers)) => headers.push(name),
+            None => modules.push((module, vec![name])),
+        }
+    }
+    modules.sort();
+
+    let mut bindings = String::from(
+        "// Generated by the build script from the LLVM headers; see build/generate.rs.\n\n",
+    );
+
+    // The root, with the types from Types.h and Core.h, and the prelude.
+    let types = run_bindgen(
+        &includedir,
+        &clang_args,
+        &["Types.h".to_owned()],
+        |builder| builder,
+    );
+    let core_types = run_bindgen(
+        &includedir,
+        &clang_args,
+        &["Core.h".to_owned()],
+        |builder| builder.ignore_functions(),
+    );
+    let type_alias = Regex::new(r"pub type (\w+)").unwrap();
+    let prelude: Vec<&str> = type_alias
+        .captures_iter(&types)
+        .map(|captures| captures.get(1).unwrap().as_str())
+        .collect();
+    bindings.push_str(&types);
+    bindings.push_str(&core_types);
+    bindings.push_str(&format!(
+        "\n/// Core types used throughout LLVM.\npub mod prelude {{\n    pub use super::{{{}}};\n}}\n",
+        prelude.join(", ")
+    ));
+
+    // The modules, with those in `transforms` nested in it.
+    let mut in_transforms = false;
+    for (module, headers) in &modules {
+        let contents = run_bindgen(&includedir, &clang_args, headers, |builder| {
+            if module == "core" {
+                // Its types are at the root.
+                builder.blocklist_type(".*")
+            } else if module == "target" {
+                // Some functions are declared by expanding the target lists.
+                builder.allowlist_file(r".*llvm/Config/.*\.def")
+            } else {
+                builder
+            }
+        });
+        let (parent, name) = match module.split_once("::") {
+            Some((parent, name)) => (Some(parent), name),
+            None => (None, &**module),
+        };
+        if in_transforms && parent.is_none() {
+            bindings.push_str("}\n");
+            in_transforms = false;
+        }
+        if parent.is_some() && !in_transforms {
+            bindings.push_str("\npub mod transforms {\n");
+            in_transforms = true;
+        }
+        bindings.push_str(&format!(
+            "\npub mod {} {{\n    #![allow(unused_imports)]\n    use crate::*;\n",
+            name
+        ));
+        for (other, _) in modules.iter().filter(|(other, _)| other != module) {
+            bindings.push_str(&format!("    use crate::{}::*;\n", other));
+        }
+        bindings.push_str(&contents);
+        if module == "target" {
+            bindings.push_str(TARGET_WRAPPERS);
+        }
+        bindings.push_str("}\n");
+    }
+    if in_transforms {
+        bindings.push_str("}\n");
+    }
+
+    println!(
+        "Generated bindings from {} LLVM headers with bindgen",
+        modules
+            .iter()
+            .map(|(_, headers)| headers.len())
+            .sum::<usize>()
+            + 1
+    );
+    fs::write(out_dir.join(BINDINGS_FILE), bindings)
+        .expect("Failed to write the generated bindings");
+}
+
+/// Run bindgen on `headers` (relative to `includedir/llvm-c`), keeping only what they
+/// define themselves, after making any changes to the builder with `configure`.
+fn run_bindgen(
+    includedir: &Path,
+    clang_args: &[String],
+    headers: &[String],
+    configure: impl FnOnce(bindgen::Builder) -> bindgen::Builder,
+) -> String {
+    let mut builder = bindgen::Builder::default()
+        .clang_args(clang_args)
+        .ctypes_prefix("::libc")
+        .default_enum_style(bindgen::EnumVariation::Rust {
+            non_exhaustive: false,
+        })
+        .prepend_enum_name(false)
+        .allowlist_recursively(false)
+        .layout_tests(false)
+        .formatter(bindgen::Formatter::None)
+        .parse_callbacks(Box::new(bindgen::CargoCallbacks::new()));
+    for header in headers {
+        builder = builder
+            .header(includedir.join("llvm-c").join(header).to_string_lossy())
+            .allowlist_file(format!(".*llvm-c/{}", regex::escape(header)));
+    }
+    let bindings = configure(builder).generate().unwrap_or_else(|e| {
+        panic!(
+            "llvm-sys-featured: bindgen failed on {}: {}",
+            headers.join(", "),
+            e
+        )
+    });
+    bindings.to_string()
+}
+
+/// The module for a header without one in `MODULES`, such as `transforms::pass_builder`
+/// for `Transforms/PassBuilder.h`.
+fn module_name(header: &str) -> String {
+    let (parent, stem) = match header.trim_end_matches(".h").split_once('/') {
+        Some((_, stem)) => ("transforms::", stem),
+        None => ("", header.trim_end_matches(".h")),
+    };
+    let mut name = String::from(parent);
+    let mut previous_lowercase = false;
+    for c in stem.chars() {
+        if c.is_ascii_uppercase() && previous_lowercase {
+            name.push('_');
+        }
+        previous_lowercase = c.is_ascii_lowercase() || c.is_ascii_digit();
+        name.push(c.to_ascii_lowercase());
+    }
+    name
+}
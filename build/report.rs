@@ -0,0 +1,67 @@
+//! A record of how we looked for LLVM.
+//!
+//! Everything discovery tries goes into the report: each candidate, its
+//! version, and why it was or wasn't used, and libclang for the `bindgen`
+//! feature. If no LLVM (or libclang) is found the report is shown as cargo
+//! warnings, and `lib.rs` includes it in its `compile_error!`
+//! (Cargo hides plain build script output unless the build fails).
+
+use std::fs;
+use std::path::Path;
+
+/// The name of the file in `OUT_DIR` that `lib.rs` includes.
+static REPORT_FILE: &str = "llvm-discovery.txt";
+
+#[derive(Debug, Default)]
+pub struct Report {
+    lines: Vec<String>,
+}
+
+impl Report {
+    /// Record one step of the search.
+    pub fn add(&mut self, line: impl Into<String>) {
+        self.lines.push(line.into());
+    }
+
+    /// Print the report to the build script output, as cargo warnings if
+    /// `warn` is set (they are shown even when the build succeeds).
+    pub fn print(&self, warn: bool) {
+        for line in &self.lines {
+            if warn {
+                println!("cargo:warning={}", line);
+            } else {
+                println!("{}", line);
+            }
+        }
+    }
+
+    /// Write the message for `lib.rs` to show when no suitable LLVM was
+    /// found.
+    pub fn write_not_found(&self, out_dir: &Path) {
+        self.write_failure(
+            out_dir,
+            "No suitable version of LLVM was found.",
+            "Set LLVM_SYS_FEATURED_CONFIG to the llvm-config to use, or \
+             LLVM_SYS_FEATURED_PREFIX to the install prefix of an LLVM of the right version. \
+             Consider using `llvmenv` to compile an appropriate copy of LLVM, and \
+             refer to the llvm-sys-featured documentation for more information.\n\n\
+             llvm-sys-featured: https://crates.io/crates/llvm-sys-featured\n\
+             llvmenv: https://crates.io/crates/llvmenv",
+        );
+    }
+
+    /// Write the message for `lib.rs` to show when the build can't go on:
+    /// `headline`, then the report, then `advice`.
+    pub fn write_failure(&self, out_dir: &Path, headline: &str, advice: &str) {
+        let mut message = format!("{} This is what llvm-sys-featured tried:\n\n", headline);
+        for line in &self.lines {
+            message.push_str("  - ");
+            message.push_str(line);
+            message.push('\n');
+        }
+        message.push('\n');
+        message.push_str(advice);
+        fs::write(out_dir.join(REPORT_FILE), message)
+            .expect("Failed to write the LLVM discovery report");
+    }
+}
@@ -0,0 +1,235 @@
+//! Reading the crate's own sources: which modules are enabled, and which of
+//! the items in them are, by evaluating their `#[cfg]` attributes.
+//!
+//! This is shared by the build script (for the `abi-probe` feature) and the
+//! `check-bindings` tool, so it only uses the standard library.
+//!
+//! The cfgs are given as a list in the form of `cfg!` arguments: `NAME` for a
+//! cfg that is set, and `key="value"` for one that has a value, such as
+//! `feature="llvm-10"`.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Find the modules of the crate that are enabled by `cfgs`, as pairs of the
+/// module path (`crate::execution_engine`) and the file defining it. The
+/// crate root comes first.
+pub fn find_modules(src_dir: &Path, cfgs: &[String]) -> Vec<(String, PathBuf)> {
+    let lib = fs::read_to_string(src_dir.join("lib.rs")).expect("Failed to read src/lib.rs");
+    let mut modules = vec![(String::from("crate"), src_dir.join("lib.rs"))];
+
+    // Module declarations in lib.rs, including those inside `pub mod transforms { ... }`.
+    let mut parent = None;
+    let mut attributes = vec![];
+    for line in logical_lines(&lib) {
+        let trimmed = line.trim();
+        if trimmed.starts_with("#[") {
+            attributes.push(trimmed.to_owned());
+            continue;
+        }
+        if let Some(rest) = trimmed.strip_prefix("pub mod ") {
+            let name: String = rest.chars().take_while(|&c| is_ident_char(c)).collect();
+            let rest = rest[name.len()..].trim();
+            if !attributes_enabled(&attributes, cfgs) {
+                // Skip the whole module.
+            } else if rest == "{" {
+                parent = Some(name);
+            } else if rest == ";" {
+                let (path, file) = match parent {
+                    Some(ref parent) => (
+                        format!("crate::{}::{}", parent, name),
+                        src_dir.join(parent).join(format!("{}.rs", name)),
+                    ),
+                    None => (format!("crate::{}", name), module_file(src_dir, &name)),
+                };
+                modules.push((path, file));
+            }
+        } else if line == "}" {
+            parent = None;
+        }
+        attributes.clear();
+    }
+    modules
+}
+
+/// The file of a top-level module: `name.rs`, or else `name/mod.rs`.
+fn module_file(src_dir: &Path, name: &str) -> PathBuf {
+    let file = src_dir.join(format!("{}.rs", name));
+    if file.is_file() {
+        file
+    } else {
+        src_dir.join(name).join("mod.rs")
+    }
+}
+
+/// A function declared in an `extern "C"` block, or an `extern_c!` block
+/// (see `lib.rs`), in the crate's sources.
+pub struct ExternFunction {
+    #[allow(dead_code)] // only used by the build script
+    pub name: String,
+    /// The whole declaration on one line, e.g. `pub fn LLVMFoo(M: LLVMModuleRef);`.
+    #[allow(dead_code)] // only used by check-bindings
+    pub declaration: String,
+    /// The attributes of the block it is in.
+    #[allow(dead_code)] // only used by the build script
+    pub block_attributes: Vec<String>,
+    /// The attributes of the declaration itself.
+    #[allow(dead_code)] // only used by the build script
+    pub attributes: Vec<String>,
+}
+
+/// Find the functions declared in the extern blocks of a module that are
+/// enabled by `cfgs`.
+pub fn extern_functions(source: &str, cfgs: &[String]) -> Vec<ExternFunction> {
+    let mut functions = vec![];
+    let mut attributes = vec![];
+    let mut block_attributes = None;
+    let mut declaration: Option<String> = None;
+    for line in logical_lines(source) {
+        let trimmed = line.trim();
+        if let Some(ref mut text) = declaration {
+            text.push(' ');
+            text.push_str(trimmed);
+        } else if trimmed.starts_with("#[") {
+            attributes.push(trimmed.to_owned());
+            continue;
+        } else if trimmed.starts_with("//") || trimmed.is_empty() {
+            continue;
+        } else if block_attributes.is_none() {
+            if trimmed.starts_with("extern \"C\" {") || trimmed.starts_with("extern_c! {") {
+                block_attributes = Some(attributes.clone());
+            }
+            attributes.clear();
+            continue;
+        } else if line == "}" {
+            block_attributes = None;
+            attributes.clear();
+            continue;
+        } else if trimmed.starts_with("pub fn ") {
+            declaration = Some(trimmed.to_owned());
+        }
+
+        if !matches!(declaration, Some(ref text) if text.ends_with(';')) {
+            continue;
+        }
+        let text = declaration.take().unwrap();
+        let block = block_attributes.as_ref().unwrap();
+        if attributes_enabled(block, cfgs) && attributes_enabled(&attributes, cfgs) {
+            let name = text["pub fn ".len()..]
+                .chars()
+                .take_while(|&c| is_ident_char(c))
+                .collect();
+            functions.push(ExternFunction {
+                name,
+                declaration: text,
+                block_attributes: block.clone(),
+                attributes: attributes.clone(),
+            });
+        }
+        attributes.clear();
+    }
+    functions
+}
+
+/// The lines of `source`, except that attributes spanning several lines are
+/// joined into one.
+pub fn logical_lines(source: &str) -> Vec<String> {
+    let mut lines: Vec<String> = vec![];
+    let mut depth = 0;
+    for line in source.lines() {
+        let trimmed = line.trim();
+        if depth > 0 {
+            lines.last_mut().unwrap().push_str(trimmed);
+        } else if trimmed.starts_with("#[") {
+            lines.push(trimmed.to_owned());
+        } else {
+            lines.push(line.to_owned());
+            continue;
+        }
+        for c in trimmed.chars() {
+            match c {
+                '[' | '(' => depth += 1,
+                ']' | ')' => depth -= 1,
+                _ => {}
+            }
+        }
+    }
+    lines
+}
+
+/// Check whether all `#[cfg(...)]` attributes among `attributes` are
+/// satisfied. Other attributes are ignored.
+pub fn attributes_enabled(attributes: &[String], cfgs: &[String]) -> bool {
+    attributes
+        .iter()
+        .filter_map(|a| a.strip_prefix("#[cfg(")?.strip_suffix(")]"))
+        .all(|predicate| {
+            let tokens = tokenize(predicate);
+            let mut tokens = tokens.iter().map(String::as_str).peekable();
+            evaluate(&mut tokens, cfgs)
+        })
+}
+
+pub fn is_ident_char(c: char) -> bool {
+    c.is_ascii_alphanumeric() || c == '_'
+}
+
+/// Split a cfg predicate into identifiers, string literals and punctuation.
+fn tokenize(predicate: &str) -> Vec<String> {
+    let mut tokens = vec![];
+    let mut chars = predicate.chars().peekable();
+    while let Some(c) = chars.next() {
+        if is_ident_char(c) {
+            let mut token = c.to_string();
+            while let Some(&c) = chars.peek().filter(|&&c| is_ident_char(c)) {
+                token.push(c);
+                chars.next();
+            }
+            tokens.push(token);
+        } else if c == '"' {
+            let mut token = c.to_string();
+            for c in chars.by_ref() {
+                token.push(c);
+                if c == '"' {
+                    break;
+                }
+            }
+            tokens.push(token);
+        } else if !c.is_whitespace() {
+            tokens.push(c.to_string());
+        }
+    }
+    tokens
+}
+
+/// Evaluate one cfg predicate, consuming its tokens.
+fn evaluate<'a>(
+    tokens: &mut std::iter::Peekable<impl Iterator<Item = &'a str>>,
+    cfgs: &[String],
+) -> bool {
+    let name = tokens.next().expect("Unexpected end of cfg predicate");
+    match name {
+        "all" | "any" | "not" => {
+            assert_eq!(tokens.next(), Some("("), "Malformed cfg predicate");
+            let mut results = vec![];
+            while tokens.peek() != Some(&")") {
+                results.push(evaluate(tokens, cfgs));
+                if tokens.peek() == Some(&",") {
+                    tokens.next();
+                }
+            }
+            tokens.next();
+            match name {
+                "all" => results.iter().all(|&r| r),
+                "any" => results.iter().any(|&r| r),
+                _ => !results[0],
+            }
+        }
+        _ if tokens.peek() == Some(&"=") => {
+            tokens.next();
+            let value = tokens.next().expect("Malformed cfg predicate");
+            cfgs.contains(&format!("{}={}", name, value))
+        }
+        _ => cfgs.iter().any(|cfg| cfg == name),
+    }
+}
@@ -0,0 +1,347 @@
+//! Building LLVM from a local source tree, for the `vendored` feature.
+//!
+//! When no installed LLVM is found, the LLVM source tree named by
+//! `LLVM_SYS_FEATURED_SOURCE_DIR` is built with CMake (and Ninja, unless
+//! `CMAKE_GENERATOR` says otherwise), and the `llvm-config` in its build tree
+//! is used like any other. Nothing is downloaded. Only the libraries and
+//! `llvm-config` are built, with the target backends selected by
+//! `component-*` features, or just the host's if none are.
+//!
+//! The build tree is kept in `OUT_DIR`, or under `LLVM_SYS_FEATURED_BUILD_CACHE`
+//! so that it can be shared between projects and survive `cargo clean`. Each
+//! source tree and set of options gets its own directory there, which later
+//! builds bring up to date instead of starting over.
+
+use std::env;
+use std::fs::{self, File};
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use semver::Version;
+
+use super::cmake::read_variables;
+use super::report::Report;
+use super::{
+    get_selected_components, is_compatible_llvm, is_cross_compiling, version_requirement,
+    ENV_BUILD_CACHE, ENV_PREFER_DYNAMIC, ENV_SOURCE_DIR,
+};
+
+/// The names LLVM's build uses for the backends in `TARGET_COMPONENTS`, which
+/// are the same up to case.
+static TARGET_NAMES: &[&str] = &[
+    "AArch64",
+    "AMDGPU",
+    "ARM",
+    "BPF",
+    "Hexagon",
+    "Lanai",
+    "Mips",
+    "MSP430",
+    "NVPTX",
+    "PowerPC",
+    "RISCV",
+    "Sparc",
+    "SystemZ",
+    "WebAssembly",
+    "X86",
+    "XCore",
+];
+
+/// Build the LLVM in `LLVM_SYS_FEATURED_SOURCE_DIR` if it is compatible with
+/// the selected version, returning the path to its `llvm-config`. What was
+/// tried is recorded in `report`; a failed build panics, so that its output is
+/// shown.
+pub fn build(selected_version: &Version, report: &mut Report) -> Option<PathBuf> {
+    let source_dir = match env::var_os(ENV_SOURCE_DIR) {
+        Some(dir) => PathBuf::from(dir),
+        None => {
+            report.add(format!(
+                "vendored feature enabled, but {} is not set",
+                ENV_SOURCE_DIR
+            ));
+            return None;
+        }
+    };
+    // Either a checkout of llvm-project, or its llvm directory.
+    let llvm_dir = if source_dir.join("llvm").join("CMakeLists.txt").is_file() {
+        source_dir.join("llvm")
+    } else {
+        source_dir
+    };
+
+    let version = match source_version(&llvm_dir) {
+        Some(version) => version,
+        None => {
+            report.add(format!(
+                "{} (from {}): not an LLVM source tree",
+                llvm_dir.display(),
+                ENV_SOURCE_DIR
+            ));
+            return None;
+        }
+    };
+    if !is_compatible_llvm(&version, selected_version) {
+        report.add(format!(
+            "{} (from {}): LLVM {} source, rejected (need {})",
+            llvm_dir.display(),
+            ENV_SOURCE_DIR,
+            version,
+            version_requirement(selected_version)
+        ));
+        return None;
+    }
+    if is_cross_compiling() {
+        report.add(format!(
+            "{} (from {}): LLVM {} source, not built (vendored builds are only for the build host)",
+            llvm_dir.display(),
+            ENV_SOURCE_DIR,
+            version
+        ));
+        return None;
+    }
+
+    let selected_components = get_selected_components();
+    let targets: Vec<&str> = TARGET_NAMES
+        .iter()
+        .cloned()
+        .filter(|name| {
+            selected_components
+                .iter()
+                .any(|component| name.eq_ignore_ascii_case(component))
+        })
+        .collect();
+    let dynamic = cfg!(feature = "prefer-dynamic") || env::var_os(ENV_PREFER_DYNAMIC).is_some();
+
+    let mut options = vec![
+        "-DCMAKE_BUILD_TYPE=Release".to_owned(),
+        format!(
+            "-DLLVM_TARGETS_TO_BUILD={}",
+            if targets.is_empty() {
+                "host".to_owned()
+            } else {
+                targets.join(";")
+            }
+        ),
+        // Only the libraries; llvm-config is built on its own below.
+        "-DLLVM_BUILD_TOOLS=OFF".to_owned(),
+        "-DLLVM_INCLUDE_TESTS=OFF".to_owned(),
+        "-DLLVM_INCLUDE_EXAMPLES=OFF".to_owned(),
+        "-DLLVM_INCLUDE_BENCHMARKS=OFF".to_owned(),
+        "-DLLVM_INCLUDE_DOCS=OFF".to_owned(),
+        "-DLLVM_ENABLE_BINDINGS=OFF".to_owned(),
+        // Don't pick up optional system libraries, which whatever links
+        // against this LLVM would then need as well.
+        "-DLLVM_ENABLE_TERMINFO=OFF".to_owned(),
+        "-DLLVM_ENABLE_ZLIB=OFF".to_owned(),
+        "-DLLVM_ENABLE_ZSTD=OFF".to_owned(),
+        "-DLLVM_ENABLE_LIBXML2=OFF".to_owned(),
+    ];
+    if dynamic {
+        options.push("-DLLVM_BUILD_LLVM_DYLIB=ON".to_owned());
+    }
+    let generator = env::var("CMAKE_GENERATOR").ok();
+    if generator.is_none() {
+        options.push("-GNinja".to_owned());
+    }
+
+    // One build directory for each source tree and set of options.
+    let key = format!(
+        "{}\n{}\n{}",
+        fs::canonicalize(&llvm_dir)
+            .unwrap_or_else(|_| llvm_dir.clone())
+            .display(),
+        options.join("\n"),
+        generator.unwrap_or_default()
+    );
+    let cache_dir = match env::var_os(ENV_BUILD_CACHE) {
+        Some(dir) => PathBuf::from(dir),
+        None => PathBuf::from(env::var_os("OUT_DIR").unwrap()).join("llvm-build"),
+    };
+    let name = format!("llvm-{}-{:016x}", version, fnv1a(key.as_bytes()));
+    let build_dir = cache_dir.join(&name);
+    fs::create_dir_all(&build_dir).unwrap_or_else(|e| {
+        panic!(
+            "llvm-sys-featured: Failed to create {}: {}",
+            build_dir.display(),
+            e
+        )
+    });
+
+    // Another build may be using the same directory.
+    let lock = File::create(cache_dir.join(format!("{}.lock", name)))
+        .and_then(|lock| unsafe { sys::lock(&lock) }.map(|()| lock))
+        .unwrap_or_else(|e| {
+            panic!(
+                "llvm-sys-featured: Failed to lock {}: {}",
+                build_dir.display(),
+                e
+            )
+        });
+
+    // Cargo only shows the output of a build script that fails, but this
+    // takes long enough to be worth explaining.
+    println!(
+        "cargo:warning=Building LLVM {} from {} in {}",
+        version,
+        llvm_dir.display(),
+        build_dir.display()
+    );
+    if !build_dir.join("CMakeCache.txt").is_file() {
+        let mut configure = Command::new("cmake");
+        configure
+            .arg("-S")
+            .arg(&llvm_dir)
+            .arg("-B")
+            .arg(&build_dir)
+            .args(&options);
+        run(configure);
+    }
+    let mut build_targets = vec![None, Some("llvm-config")];
+    if dynamic {
+        build_targets.push(Some("LLVM"));
+    }
+    for target in build_targets {
+        let mut build = Command::new("cmake");
+        build.arg("--build").arg(&build_dir);
+        if let Some(target) = target {
+            build.arg("--target").arg(target);
+        }
+        if let Ok(jobs) = env::var("NUM_JOBS") {
+            build.arg("--parallel").arg(jobs);
+        }
+        run(build);
+    }
+    drop(lock);
+
+    let llvm_config = build_dir
+        .join("bin")
+        .join(format!("llvm-config{}", env::consts::EXE_SUFFIX));
+    report.add(format!(
+        "{} (from {}): LLVM {} source, built in {}, used",
+        llvm_dir.display(),
+        ENV_SOURCE_DIR,
+        version,
+        build_dir.display()
+    ));
+    Some(llvm_config)
+}
+
+/// The version of LLVM in a source tree, from its top-level CMakeLists.txt
+/// (or `cmake/Modules/LLVMVersion.cmake` next to it, in newer releases).
+fn source_version(llvm_dir: &Path) -> Option<Version> {
+    let files = [
+        llvm_dir.join("CMakeLists.txt"),
+        llvm_dir
+            .join("..")
+            .join("cmake")
+            .join("Modules")
+            .join("LLVMVersion.cmake"),
+    ];
+    files.iter().find_map(|file| {
+        let variables = read_variables(file, Default::default())?;
+        let component = |name: &str| variables.get(name)?.parse::<u64>().ok();
+        Some(Version::new(
+            component("LLVM_VERSION_MAJOR")?,
+            component("LLVM_VERSION_MINOR")?,
+            component("LLVM_VERSION_PATCH")?,
+        ))
+    })
+}
+
+/// Run a CMake command, with its output going to the build script's (which
+/// Cargo shows if it fails).
+fn run(mut command: Command) {
+    println!("cargo:warning=Running {:?}", command);
+    match command.status() {
+        Ok(status) if status.success() => {}
+        Ok(status) => panic!(
+            "llvm-sys-featured: Building LLVM failed: {:?} exited with {}",
+            command, status
+        ),
+        Err(e) => panic!(
+            "llvm-sys-featured: Building LLVM failed: could not run cmake: {}",
+            e
+        ),
+    }
+}
+
+/// A hash that is the same in every build, unlike std's.
+fn fnv1a(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0xcbf2_9ce4_8422_2325, |hash, &byte| {
+        (hash ^ u64::from(byte)).wrapping_mul(0x0100_0000_01b3)
+    })
+}
+
+// Locking the lock file, which is released when it is closed (even if the
+// build script is killed). `File::lock` does this too, but needs Rust 1.89.
+#[cfg(unix)]
+mod sys {
+    use std::fs::File;
+    use std::io;
+    use std::os::unix::io::AsRawFd;
+
+    pub unsafe fn lock(file: &File) -> io::Result<()> {
+        loop {
+            if libc::flock(file.as_raw_fd(), libc::LOCK_EX) == 0 {
+                return Ok(());
+            }
+            let error = io::Error::last_os_error();
+            if error.kind() != io::ErrorKind::Interrupted {
+                return Err(error);
+            }
+        }
+    }
+}
+
+#[cfg(windows)]
+mod sys {
+    use std::fs::File;
+    use std::io;
+    use std::os::windows::io::AsRawHandle;
+
+    #[repr(C)]
+    struct Overlapped {
+        internal: usize,
+        internal_high: usize,
+        offset: u32,
+        offset_high: u32,
+        event: *mut libc::c_void,
+    }
+
+    const LOCKFILE_EXCLUSIVE_LOCK: u32 = 2;
+
+    #[link(name = "kernel32")]
+    extern "system" {
+        fn LockFileEx(
+            file: *mut libc::c_void,
+            flags: u32,
+            reserved: u32,
+            bytes_low: u32,
+            bytes_high: u32,
+            overlapped: *mut Overlapped,
+        ) -> i32;
+    }
+
+    pub unsafe fn lock(file: &File) -> io::Result<()> {
+        let mut overlapped = Overlapped {
+            internal: 0,
+            internal_high: 0,
+            offset: 0,
+            offset_high: 0,
+            event: std::ptr::null_mut(),
+        };
+        let locked = LockFileEx(
+            file.as_raw_handle() as *mut libc::c_void,
+            LOCKFILE_EXCLUSIVE_LOCK,
+            0,
+            !0,
+            !0,
+            &mut overlapped,
+        );
+        if locked == 0 {
+            Err(io::Error::last_os_error())
+        } else {
+            Ok(())
+        }
+    }
+}
@@ -0,0 +1,67 @@
+//! Sets the cfgs of `llvm-sys-featured` for a crate that depends on it.
+//!
+//! `llvm-sys-featured` describes the LLVM it is built for with cfgs such as
+//! `LLVM_VERSION_10_OR_GREATER`, `LLVM_COMPONENT_ORC` or `LLVM_TARGET_X86`,
+//! and exports them to the build scripts of the crates that depend on it
+//! directly. Calling [`emit`] in such a build script sets the same cfgs for
+//! that crate, so that its code can be gated on exactly the same conditions as
+//! the bindings, instead of working them out again from features:
+//!
+//! ```no_run
+//! // In build.rs
+//! llvm_sys_featured_cfg::emit();
+//! ```
+//!
+//! The crate needs `llvm-sys-featured` (possibly renamed) as a dependency, and
+//! this crate as a build dependency.
+
+use std::env;
+
+/// Set the cfgs of `llvm-sys-featured` for the crate being built, and declare
+/// all of the cfgs it may set, so that `#[cfg]`s on the ones that aren't set
+/// don't warn.
+///
+/// # Panics
+///
+/// If the crate being built doesn't depend on `llvm-sys-featured` directly.
+pub fn emit() {
+    for cfg in check_cfgs() {
+        println!("cargo:rustc-check-cfg=cfg({})", cfg);
+    }
+    for cfg in cfgs() {
+        println!("cargo:rustc-cfg={}", cfg);
+    }
+}
+
+/// The cfgs that `llvm-sys-featured` sets, such as
+/// `LLVM_VERSION_10_OR_GREATER`.
+///
+/// # Panics
+///
+/// If the crate being built doesn't depend on `llvm-sys-featured` directly.
+pub fn cfgs() -> Vec<String> {
+    split(&dep_var("DEP_LLVM_CFGS"))
+}
+
+/// Every cfg that `llvm-sys-featured` may set, whether or not it does.
+///
+/// # Panics
+///
+/// If the crate being built doesn't depend on `llvm-sys-featured` directly.
+pub fn check_cfgs() -> Vec<String> {
+    split(&dep_var("DEP_LLVM_CHECK_CFGS"))
+}
+
+fn dep_var(name: &str) -> String {
+    env::var(name).unwrap_or_else(|_| {
+        panic!(
+            "llvm-sys-featured-cfg: {} is not set. This only works in the build script of a \
+             crate that depends on llvm-sys-featured directly.",
+            name
+        )
+    })
+}
+
+fn split(value: &str) -> Vec<String> {
+    value.split_whitespace().map(str::to_owned).collect()
+}
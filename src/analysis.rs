@@ -13,7 +13,7 @@ pub enum LLVMVerifierFailureAction {
     LLVMReturnStatusAction = 2,
 }
 
-extern "C" {
+extern_c! {
     /// Verify that a module is valid, taking the specified action if not.
     ///
     /// Optionally returns a human-readable description of any invalid constructs,
@@ -0,0 +1,562 @@
+//! Compare the bindings with the LLVM headers they are written against.
+//!
+//! Every function prototype in the installed `llvm-c` headers (and in the C
+//! wrappers in `wrappers/`) is compared with the `extern "C"` declarations in
+//! `src`, taking into account the `#[cfg]`s the crate is built with. The
+//! differences are reported: functions missing from the bindings, bindings for
+//! functions the headers don't declare, and bindings with the wrong number of
+//! parameters or levels of pointer indirection.
+//!
+//! Run it with the feature for the LLVM version to check, for example
+//!
+//! ```text
+//! cargo run --features llvm-10 --bin check-bindings
+//! ```
+//!
+//! It reads the headers of the LLVM that the build script found, unless given
+//! the path of another include directory. Don't enable any `component-*`
+//! features, or the bindings that need the components left out are reported
+//! as missing.
+
+use std::collections::{BTreeMap, HashMap};
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process;
+
+#[path = "../../build/source.rs"]
+mod source;
+
+use source::{extern_functions, find_modules, is_ident_char};
+
+/// A parameter or return type.
+#[derive(Debug)]
+struct Type {
+    /// The type as written.
+    text: String,
+    /// The number of levels of pointer indirection.
+    pointers: usize,
+}
+
+/// A function declared in a header or in the bindings.
+#[derive(Debug)]
+struct Function {
+    params: Vec<Type>,
+    ret: Type,
+    /// The file declaring the function.
+    location: String,
+}
+
+fn main() {
+    let manifest_dir = Path::new(env!("CARGO_MANIFEST_DIR"));
+    let (cfgs, default_includedir) = match (
+        option_env!("LLVM_SYS_FEATURED_CFGS"),
+        option_env!("LLVM_SYS_FEATURED_INCLUDEDIR"),
+    ) {
+        (Some(cfgs), Some(includedir)) => (cfgs, includedir),
+        _ => {
+            eprintln!("check-bindings: the build script didn't find LLVM (or is in stub mode)");
+            process::exit(2);
+        }
+    };
+    let cfgs: Vec<String> = cfgs.split(' ').map(str::to_owned).collect();
+    if cfgs.iter().any(|cfg| cfg == "LLVM_SYS_BINDGEN") {
+        eprintln!("check-bindings: the bindings are generated from the headers (bindgen feature)");
+        process::exit(2);
+    }
+    let includedir = env::args_os()
+        .nth(1)
+        .map_or_else(|| PathBuf::from(default_includedir), PathBuf::from);
+
+    let mut headers = BTreeMap::new();
+    for dir in &["llvm-c", "llvm-c/Transforms"] {
+        let entries = match fs::read_dir(includedir.join(dir)) {
+            Ok(entries) => entries,
+            Err(_) => continue,
+        };
+        for path in entries
+            .filter_map(|entry| entry.ok())
+            .map(|entry| entry.path())
+        {
+            if path.extension().and_then(|ext| ext.to_str()) == Some("h") {
+                let name = path
+                    .strip_prefix(&includedir)
+                    .unwrap()
+                    .display()
+                    .to_string();
+                headers.extend(c_functions(&read(&path), &name, &includedir, false));
+            }
+        }
+    }
+    if headers.is_empty() {
+        eprintln!(
+            "check-bindings: no headers found in {}",
+            includedir.display()
+        );
+        process::exit(2);
+    }
+    // The bindings to the static inline functions in the headers are to these
+    // wrappers instead.
+    for entry in fs::read_dir(manifest_dir.join("wrappers")).expect("Failed to read wrappers/") {
+        let path = entry.expect("Failed to read wrappers/").path();
+        let name = path
+            .strip_prefix(manifest_dir)
+            .unwrap()
+            .display()
+            .to_string();
+        headers.extend(c_functions(&read(&path), &name, &includedir, true));
+    }
+
+    let mut bindings = BTreeMap::new();
+    for (_, file) in find_modules(&manifest_dir.join("src"), &cfgs) {
+        let name = file
+            .strip_prefix(manifest_dir)
+            .unwrap()
+            .display()
+            .to_string();
+        bindings.extend(rust_functions(&read(&file), &name, &cfgs));
+    }
+
+    let mut missing = vec![];
+    let mut extra = vec![];
+    let mut mismatched = vec![];
+    for (name, function) in &headers {
+        match bindings.get(name) {
+            Some(binding) => mismatched.extend(compare(name, function, binding)),
+            None => missing.push(format!("{} ({})", name, function.location)),
+        }
+    }
+    for (name, binding) in &bindings {
+        if !headers.contains_key(name) {
+            extra.push(format!("{} ({})", name, binding.location));
+        }
+    }
+
+    report("Functions missing from the bindings", &missing);
+    report("Bindings for functions not in the headers", &extra);
+    report("Bindings that don't match the headers", &mismatched);
+    println!(
+        "Compared {} functions in the headers in {} with {} bindings.",
+        headers.len(),
+        includedir.display(),
+        bindings.len()
+    );
+    if !(missing.is_empty() && extra.is_empty() && mismatched.is_empty()) {
+        process::exit(1);
+    }
+}
+
+fn read(path: &Path) -> String {
+    fs::read_to_string(path).unwrap_or_else(|e| panic!("Failed to read {}: {}", path.display(), e))
+}
+
+fn report(title: &str, lines: &[String]) {
+    if !lines.is_empty() {
+        println!("{} ({}):", title, lines.len());
+        for line in lines {
+            println!("  {}", line);
+        }
+        println!();
+    }
+}
+
+/// Describe how a binding differs from the C prototype, if it does.
+fn compare(name: &str, function: &Function, binding: &Function) -> Vec<String> {
+    let mut differences = vec![];
+    let describe = |what: String, c: &Type, rust: &Type| {
+        format!(
+            "{}: {} `{}` in {}, `{}` in {}",
+            name, what, c.text, function.location, rust.text, binding.location
+        )
+    };
+    if function.params.len() != binding.params.len() {
+        differences.push(format!(
+            "{}: {} parameters in {}, {} in {}",
+            name,
+            function.params.len(),
+            function.location,
+            binding.params.len(),
+            binding.location
+        ));
+    } else {
+        for (i, (c, rust)) in function.params.iter().zip(&binding.params).enumerate() {
+            if c.pointers != rust.pointers {
+                differences.push(describe(format!("parameter {} is", i + 1), c, rust));
+            }
+        }
+    }
+    if function.ret.pointers != binding.ret.pointers {
+        differences.push(describe("returns".to_owned(), &function.ret, &binding.ret));
+    }
+    differences
+}
+
+/// Find the functions declared (or, with `definitions`, defined) in C source.
+/// Static inline functions in headers aren't exported by LLVM, so they are
+/// skipped.
+fn c_functions(
+    source: &str,
+    location: &str,
+    includedir: &Path,
+    definitions: bool,
+) -> Vec<(String, Function)> {
+    let code = preprocess(&strip_comments(source), includedir, &mut HashMap::new());
+    let mut functions = vec![];
+    for statement in c_statements(&code) {
+        let statement = unwrap_deprecated(&statement);
+        let (declaration, is_definition) = match statement.find('{') {
+            Some(brace) => (&statement[..brace], true),
+            None => (&*statement, false),
+        };
+        let declaration = declaration.trim();
+        if is_definition != definitions
+            || declaration.starts_with("typedef")
+            || declaration.starts_with("static")
+            || declaration.contains('=')
+        {
+            continue;
+        }
+        let open = match declaration.find('(') {
+            Some(open) => open,
+            None => continue,
+        };
+        let before = declaration[..open].trim_end();
+        let name_start = before.rfind(|c| !is_ident_char(c)).map_or(0, |i| i + 1);
+        let name = &before[name_start..];
+        let ret = before[..name_start]
+            .trim()
+            .trim_start_matches("extern ")
+            .trim();
+        let close = match matching_paren(declaration, open) {
+            Some(close) => close,
+            None => continue,
+        };
+        if name.is_empty() || ret.is_empty() {
+            continue;
+        }
+        let params = split_top_level(&declaration[open + 1..close])
+            .into_iter()
+            .filter(|param| param != "void")
+            .map(|param| c_type(&param))
+            .collect();
+        functions.push((
+            name.to_owned(),
+            Function {
+                params,
+                ret: c_type(ret),
+                location: location.to_owned(),
+            },
+        ));
+    }
+    functions
+}
+
+/// The type of a C parameter or return type, with the parameter name.
+fn c_type(text: &str) -> Type {
+    // A function pointer is bound as a function pointer (or an `Option` of
+    // one), not a raw pointer.
+    let pointers = if text.contains("(*") {
+        0
+    } else {
+        text.matches(['*', '[']).count()
+    };
+    Type {
+        text: text.to_owned(),
+        pointers,
+    }
+}
+
+fn strip_comments(source: &str) -> String {
+    let mut code = String::with_capacity(source.len());
+    let mut chars = source.chars().peekable();
+    while let Some(c) = chars.next() {
+        match (c, chars.peek()) {
+            ('/', Some('/')) => {
+                for c in chars.by_ref() {
+                    if c == '\n' {
+                        code.push('\n');
+                        break;
+                    }
+                }
+            }
+            ('/', Some('*')) => {
+                chars.next();
+                let mut previous = ' ';
+                for c in chars.by_ref() {
+                    if previous == '*' && c == '/' {
+                        break;
+                    }
+                    if c == '\n' {
+                        code.push('\n');
+                    }
+                    previous = c;
+                }
+                code.push(' ');
+            }
+            ('"', _) => {
+                code.push(c);
+                while let Some(c) = chars.next() {
+                    code.push(c);
+                    if c == '\\' {
+                        code.extend(chars.next());
+                    } else if c == '"' {
+                        break;
+                    }
+                }
+            }
+            _ => code.push(c),
+        }
+    }
+    code
+}
+
+/// Remove preprocessor directives, dropping the C++-only parts of the
+/// headers, and expand the function-like macros the headers use to declare
+/// families of functions, such as `LLVMIsA*` and the target initialization
+/// functions (whose targets are listed in `llvm/Config/*.def`).
+fn preprocess(
+    code: &str,
+    includedir: &Path,
+    macros: &mut HashMap<String, (String, String)>,
+) -> String {
+    let mut output = String::new();
+    // For each open conditional, whether it is C++-only, and whether we are
+    // in the part being skipped.
+    let mut conditionals: Vec<(bool, bool)> = vec![];
+    let mut lines = code.lines();
+    while let Some(line) = lines.next() {
+        let mut line = line.trim().to_owned();
+        while line.ends_with('\\') {
+            line.pop();
+            line.push(' ');
+            line.push_str(lines.next().unwrap_or_default().trim());
+        }
+        let skipping = conditionals.iter().any(|&(_, skip)| skip);
+        let directive = match line.strip_prefix('#') {
+            Some(directive) => directive.trim_start(),
+            None => {
+                if !skipping {
+                    output.push_str(&expand_macros(&line, macros));
+                    output.push('\n');
+                }
+                continue;
+            }
+        };
+        let cplusplus = directive.contains("__cplusplus");
+        if directive.starts_with("if") {
+            conditionals.push((cplusplus, cplusplus && !directive.starts_with("ifndef")));
+        } else if directive.starts_with("else") || directive.starts_with("elif") {
+            if let Some(last) = conditionals.last_mut() {
+                if last.0 {
+                    last.1 = !last.1;
+                }
+            }
+        } else if directive.starts_with("endif") {
+            conditionals.pop();
+        } else if skipping {
+            continue;
+        } else if let Some(definition) = directive.strip_prefix("define") {
+            // Only function-like macros with one parameter are needed.
+            let definition = definition.trim();
+            if let (Some(open), Some(close)) = (definition.find('('), definition.find(')')) {
+                let name = &definition[..open];
+                let param = definition[open + 1..close].trim();
+                if !name.is_empty() && name.chars().chain(param.chars()).all(is_ident_char) {
+                    let body = definition[close + 1..].trim().to_owned();
+                    macros.insert(name.to_owned(), (param.to_owned(), body));
+                }
+            }
+        } else if let Some(include) = directive.strip_prefix("include") {
+            let file = include
+                .trim()
+                .trim_matches(|c| c == '"' || c == '<' || c == '>');
+            if file.starts_with("llvm/Config/") && file.ends_with(".def") {
+                let def = fs::read_to_string(includedir.join(file)).unwrap_or_default();
+                output.push_str(&preprocess(&strip_comments(&def), includedir, macros));
+            }
+        }
+    }
+    output
+}
+
+/// Expand the uses of `macros` in `code`, including uses that result from
+/// expanding other macros.
+fn expand_macros(code: &str, macros: &HashMap<String, (String, String)>) -> String {
+    let mut code = code.to_owned();
+    for _ in 0..8 {
+        let mut output = String::new();
+        let mut rest = &*code;
+        while let Some(start) = rest.find(is_ident_char) {
+            output.push_str(&rest[..start]);
+            rest = &rest[start..];
+            let end = rest.find(|c| !is_ident_char(c)).unwrap_or(rest.len());
+            let (word, after) = rest.split_at(end);
+            let close = matching_paren(after, 0).filter(|_| after.starts_with('('));
+            match (macros.get(word), close) {
+                (Some((param, body)), Some(close)) => {
+                    let arg = after[1..close].trim();
+                    output.push_str(&substitute(body, param, arg));
+                    output.push(' ');
+                    rest = &after[close + 1..];
+                }
+                _ => {
+                    output.push_str(word);
+                    rest = after;
+                }
+            }
+        }
+        output.push_str(rest);
+        if output == code {
+            break;
+        }
+        code = output;
+    }
+    code
+}
+
+/// Substitute `arg` for `param` in a macro body, and paste tokens.
+fn substitute(body: &str, param: &str, arg: &str) -> String {
+    let mut output = String::new();
+    let mut word = String::new();
+    for c in body.chars().chain(Some(' ')) {
+        if is_ident_char(c) {
+            word.push(c);
+            continue;
+        }
+        output.push_str(if word == param { arg } else { &word });
+        word.clear();
+        output.push(c);
+    }
+    output.replace(" ## ", "").replace("##", "")
+}
+
+/// Split C code into top-level statements: declarations ending with `;`, and
+/// definitions ending with `}`.
+fn c_statements(code: &str) -> Vec<String> {
+    let mut statements = vec![];
+    let mut current = String::new();
+    let mut depth = 0;
+    for c in code.chars() {
+        current.push(if c == '\n' { ' ' } else { c });
+        match c {
+            '{' => depth += 1,
+            '}' => {
+                depth -= 1;
+                if depth == 0 {
+                    statements.push(current.trim().to_owned());
+                    current.clear();
+                }
+            }
+            ';' if depth == 0 => {
+                statements.push(current.trim_end_matches(';').trim().to_owned());
+                current.clear();
+            }
+            _ => {}
+        }
+    }
+    statements
+}
+
+/// `LLVM_ATTRIBUTE_C_DEPRECATED(decl, message)` is `decl` for our purposes.
+fn unwrap_deprecated(statement: &str) -> String {
+    let statement = statement.trim_start_matches("LLVM_C_EXTERN_C_BEGIN").trim();
+    let inner = match statement.strip_prefix("LLVM_ATTRIBUTE_C_DEPRECATED") {
+        Some(inner) => inner.trim_start(),
+        None => return statement.to_owned(),
+    };
+    match split_top_level(&inner[1..inner.len() - 1]).first() {
+        Some(declaration) => declaration.clone(),
+        None => statement.to_owned(),
+    }
+}
+
+/// Find the parenthesis closing the one at `open`.
+fn matching_paren(text: &str, open: usize) -> Option<usize> {
+    let mut depth = 0;
+    for (i, c) in text[open..].char_indices() {
+        match c {
+            '(' => depth += 1,
+            ')' => {
+                depth -= 1;
+                if depth == 0 {
+                    return Some(open + i);
+                }
+            }
+            _ => {}
+        }
+    }
+    None
+}
+
+/// Split a list on the commas that aren't nested in brackets, dropping empty
+/// elements.
+fn split_top_level(list: &str) -> Vec<String> {
+    let mut elements = vec![];
+    let mut current = String::new();
+    let mut depth = 0;
+    for c in list.chars() {
+        match c {
+            '(' | '[' | '<' => depth += 1,
+            ')' | ']' | '>' if !current.ends_with('-') => depth -= 1,
+            ',' if depth == 0 => {
+                elements.push(current.trim().to_owned());
+                current.clear();
+                continue;
+            }
+            _ => {}
+        }
+        current.push(c);
+    }
+    elements.push(current.trim().to_owned());
+    elements.retain(|element| !element.is_empty());
+    elements
+}
+
+/// Find the functions declared in the extern blocks of a Rust module that are
+/// enabled by `cfgs`.
+fn rust_functions(source: &str, location: &str, cfgs: &[String]) -> Vec<(String, Function)> {
+    extern_functions(source, cfgs)
+        .iter()
+        .filter_map(|function| parse_rust_function(&function.declaration, location))
+        .collect()
+}
+
+/// Parse `pub fn Name(params) -> Ret;`.
+fn parse_rust_function(text: &str, location: &str) -> Option<(String, Function)> {
+    let text = text.strip_prefix("pub fn ")?;
+    let open = text.find('(')?;
+    let close = matching_paren(text, open)?;
+    let params = split_top_level(&text[open + 1..close])
+        .iter()
+        .map(|param| match param.split_once(':') {
+            Some((_, ty)) => rust_type(ty.trim()),
+            None => rust_type(param),
+        })
+        .collect();
+    let ret = text[close + 1..].trim().trim_end_matches(';').trim();
+    let ret = ret.strip_prefix("->").unwrap_or("()").trim();
+    Some((
+        text[..open].trim().to_owned(),
+        Function {
+            params,
+            ret: rust_type(ret),
+            location: location.to_owned(),
+        },
+    ))
+}
+
+fn rust_type(text: &str) -> Type {
+    let mut rest = text;
+    let mut pointers = 0;
+    while let Some(pointee) = rest
+        .strip_prefix("*const ")
+        .or_else(|| rest.strip_prefix("*mut "))
+    {
+        rest = pointee.trim_start();
+        pointers += 1;
+    }
+    Type {
+        text: text.to_owned(),
+        pointers,
+    }
+}
@@ -2,7 +2,7 @@
 
 use super::prelude::*;
 
-extern "C" {
+extern_c! {
     /// Build a module from the bitcode in the specified memory buffer.
     ///
     /// Returns 0 on success and the generated module in `OutModule`.
@@ -2,7 +2,7 @@
 
 use super::prelude::*;
 
-extern "C" {
+extern_c! {
     /// Write a module to the specified path.
     ///
     /// Returns 0 on success.
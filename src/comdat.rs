@@ -16,7 +16,7 @@ pub enum LLVMComdatSelectionKind {
     LLVMSameSizeComdatSelectionKind,
 }
 
-extern "C" {
+extern_c! {
     /// Return the Comdat in the module with the specified name. It is created if it didn't already exist.
     pub fn LLVMGetOrInsertComdat(M: LLVMModuleRef, Name: *const ::libc::c_char) -> LLVMComdatRef;
 
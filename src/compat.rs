@@ -0,0 +1,781 @@
+//! Version-independent wrappers for parts of the API that changed between
+//! the supported LLVM versions.
+//!
+//! Each function here makes the calls that the selected LLVM version needs,
+//! so that code using them works unchanged with every version, without
+//! `#[cfg(LLVM_VERSION_...)]`s of its own.
+
+use std::ffi::CStr;
+use std::slice;
+
+use super::core::*;
+use super::object::*;
+use super::prelude::*;
+use super::*;
+
+/// Take a message allocated by LLVM, disposing of it.
+#[cfg(LLVM_VERSION_9_OR_GREATER)]
+unsafe fn take_message(message: *mut ::libc::c_char) -> String {
+    let string = CStr::from_ptr(message).to_string_lossy().into_owned();
+    LLVMDisposeMessage(message);
+    string
+}
+
+/// Copy a string that isn't nul-terminated.
+unsafe fn copy_string(data: *const ::libc::c_char, len: u32) -> String {
+    if data.is_null() || len == 0 {
+        return String::new();
+    }
+    String::from_utf8_lossy(slice::from_raw_parts(data as *const u8, len as usize)).into_owned()
+}
+
+/// Call `f` with an iterator positioned at each section of the object file in
+/// `buffer`, in order. The iterator can be used with the `LLVMGetSection*` and
+/// `LLVMGetRelocations` functions, but only until `f` returns.
+///
+/// LLVM 8 reads object files with `LLVMCreateObjectFile`, and later versions
+/// with `LLVMCreateBinary`, which doesn't take ownership of the buffer. Here
+/// the buffer always stays owned by the caller.
+///
+/// # Safety
+///
+/// `buffer` and `context` must be valid.
+pub unsafe fn for_each_section<F: FnMut(LLVMSectionIteratorRef)>(
+    buffer: LLVMMemoryBufferRef,
+    context: LLVMContextRef,
+    mut f: F,
+) -> Result<(), String> {
+    let object = ObjectFile::open(buffer, context)?;
+    // Dropped before the object file, even if `f` panics.
+    let iterator = SectionIterator(object.sections());
+    while !object.sections_at_end(iterator.0) {
+        f(iterator.0);
+        LLVMMoveToNextSection(iterator.0);
+    }
+    Ok(())
+}
+
+/// Call `f` with an iterator positioned at each symbol of the object file in
+/// `buffer`, in order, as [`for_each_section`] does for sections. The
+/// iterator can be used with the `LLVMGetSymbol*` functions.
+///
+/// # Safety
+///
+/// `buffer` and `context` must be valid.
+pub unsafe fn for_each_symbol<F: FnMut(LLVMSymbolIteratorRef)>(
+    buffer: LLVMMemoryBufferRef,
+    context: LLVMContextRef,
+    mut f: F,
+) -> Result<(), String> {
+    let object = ObjectFile::open(buffer, context)?;
+    let iterator = SymbolIterator(object.symbols());
+    while !object.symbols_at_end(iterator.0) {
+        f(iterator.0);
+        LLVMMoveToNextSymbol(iterator.0);
+    }
+    Ok(())
+}
+
+/// A section iterator, disposed of when dropped.
+struct SectionIterator(LLVMSectionIteratorRef);
+
+impl Drop for SectionIterator {
+    fn drop(&mut self) {
+        unsafe { LLVMDisposeSectionIterator(self.0) }
+    }
+}
+
+/// A symbol iterator, disposed of when dropped.
+struct SymbolIterator(LLVMSymbolIteratorRef);
+
+impl Drop for SymbolIterator {
+    fn drop(&mut self) {
+        unsafe { LLVMDisposeSymbolIterator(self.0) }
+    }
+}
+
+/// An object file opened with the API of the selected version, and disposed
+/// of when dropped.
+#[cfg(LLVM_VERSION_8_OR_LOWER)]
+struct ObjectFile(LLVMObjectFileRef);
+
+#[cfg(LLVM_VERSION_8_OR_LOWER)]
+impl ObjectFile {
+    unsafe fn open(buffer: LLVMMemoryBufferRef, _context: LLVMContextRef) -> Result<Self, String> {
+        // LLVMCreateObjectFile takes ownership of the buffer, so give it a
+        // copy.
+        let copy = LLVMCreateMemoryBufferWithMemoryRangeCopy(
+            LLVMGetBufferStart(buffer),
+            LLVMGetBufferSize(buffer),
+            b"\0".as_ptr() as *const ::libc::c_char,
+        );
+        let object = LLVMCreateObjectFile(copy);
+        if object.is_null() {
+            return Err("The buffer doesn't hold a supported object file".to_owned());
+        }
+        Ok(ObjectFile(object))
+    }
+
+    unsafe fn sections(&self) -> LLVMSectionIteratorRef {
+        LLVMGetSections(self.0)
+    }
+
+    unsafe fn sections_at_end(&self, iterator: LLVMSectionIteratorRef) -> bool {
+        LLVMObjectFileIsSectionIteratorAtEnd(self.0, iterator) != 0
+    }
+
+    unsafe fn symbols(&self) -> LLVMSymbolIteratorRef {
+        LLVMGetSymbols(self.0)
+    }
+
+    unsafe fn symbols_at_end(&self, iterator: LLVMSymbolIteratorRef) -> bool {
+        LLVMObjectFileIsSymbolIteratorAtEnd(self.0, iterator) != 0
+    }
+}
+
+#[cfg(LLVM_VERSION_8_OR_LOWER)]
+impl Drop for ObjectFile {
+    fn drop(&mut self) {
+        unsafe { LLVMDisposeObjectFile(self.0) }
+    }
+}
+
+#[cfg(LLVM_VERSION_9_OR_GREATER)]
+struct ObjectFile(LLVMBinaryRef);
+
+#[cfg(LLVM_VERSION_9_OR_GREATER)]
+impl ObjectFile {
+    unsafe fn open(buffer: LLVMMemoryBufferRef, context: LLVMContextRef) -> Result<Self, String> {
+        let mut error = std::ptr::null_mut();
+        let binary = LLVMCreateBinary(buffer, context, &mut error);
+        if binary.is_null() {
+            return Err(take_message(error));
+        }
+        let binary = ObjectFile(binary);
+        // The object file functions don't check that they are given one.
+        match LLVMBinaryGetType(binary.0) {
+            LLVMBinaryType::LLVMBinaryTypeCOFF
+            | LLVMBinaryType::LLVMBinaryTypeELF32L
+            | LLVMBinaryType::LLVMBinaryTypeELF32B
+            | LLVMBinaryType::LLVMBinaryTypeELF64L
+            | LLVMBinaryType::LLVMBinaryTypeELF64B
+            | LLVMBinaryType::LLVMBinaryTypeMachO32L
+            | LLVMBinaryType::LLVMBinaryTypeMachO32B
+            | LLVMBinaryType::LLVMBinaryTypeMachO64L
+            | LLVMBinaryType::LLVMBinaryTypeMachO64B
+            | LLVMBinaryType::LLVMBinaryTypeWasm => Ok(binary),
+            _ => Err("The buffer doesn't hold an object file".to_owned()),
+        }
+    }
+
+    unsafe fn sections(&self) -> LLVMSectionIteratorRef {
+        LLVMObjectFileCopySectionIterator(self.0)
+    }
+
+    unsafe fn sections_at_end(&self, iterator: LLVMSectionIteratorRef) -> bool {
+        LLVMObjectFileIsSectionIteratorAtEnd(self.0, iterator) != 0
+    }
+
+    unsafe fn symbols(&self) -> LLVMSymbolIteratorRef {
+        LLVMObjectFileCopySymbolIterator(self.0)
+    }
+
+    unsafe fn symbols_at_end(&self, iterator: LLVMSymbolIteratorRef) -> bool {
+        LLVMObjectFileIsSymbolIteratorAtEnd(self.0, iterator) != 0
+    }
+}
+
+#[cfg(LLVM_VERSION_9_OR_GREATER)]
+impl Drop for ObjectFile {
+    fn drop(&mut self) {
+        unsafe { LLVMDisposeBinary(self.0) }
+    }
+}
+
+/// The kind of an optimization remark.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RemarkKind {
+    Unknown,
+    Passed,
+    Missed,
+    Analysis,
+    AnalysisFPCommute,
+    AnalysisAliasing,
+    Failure,
+}
+
+/// A location in the source code.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RemarkDebugLoc {
+    pub source_file: String,
+    pub line: u32,
+    pub column: u32,
+}
+
+/// An argument of an optimization remark.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct RemarkArg {
+    pub key: String,
+    pub value: String,
+    pub debug_loc: Option<RemarkDebugLoc>,
+}
+
+/// An optimization remark, as read by [`parse_remarks`].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Remark {
+    pub kind: RemarkKind,
+    pub pass_name: String,
+    pub remark_name: String,
+    pub function_name: String,
+    pub debug_loc: Option<RemarkDebugLoc>,
+    /// 0 if the remark has no hotness
+    pub hotness: u64,
+    pub args: Vec<RemarkArg>,
+}
+
+/// Parse the optimization remarks in `buffer`, in YAML format (as written by
+/// `-fsave-optimization-record` or `-pass-remarks-output`).
+///
+/// LLVM 8 parses them with the `LLVMOptRemark*` functions from `opt_remarks`,
+/// and later versions with the `LLVMRemark*` functions from `remarks`.
+pub fn parse_remarks(buffer: &[u8]) -> Result<Vec<Remark>, String> {
+    unsafe { parse_remarks_impl(buffer) }
+}
+
+#[cfg(LLVM_VERSION_8_OR_LOWER)]
+unsafe fn parse_remarks_impl(buffer: &[u8]) -> Result<Vec<Remark>, String> {
+    use super::opt_remarks::*;
+
+    unsafe fn string(string: &LLVMOptRemarkStringRef) -> String {
+        copy_string(string.Str, string.Len)
+    }
+
+    unsafe fn debug_loc(debug_loc: &LLVMOptRemarkDebugLoc) -> Option<RemarkDebugLoc> {
+        if debug_loc.SourceFile.Len == 0 {
+            return None;
+        }
+        Some(RemarkDebugLoc {
+            source_file: string(&debug_loc.SourceFile),
+            line: debug_loc.SourceLineNumber,
+            column: debug_loc.SourceColumnNumber,
+        })
+    }
+
+    let parser = LLVMOptRemarkParserCreate(buffer.as_ptr() as *const _, buffer.len() as u64);
+    let mut remarks = vec![];
+    loop {
+        // The entry belongs to the parser, and is only valid until the next
+        // one is read.
+        let entry = LLVMOptRemarkParserGetNext(parser);
+        if entry.is_null() {
+            break;
+        }
+        let entry = &*entry;
+        let kind = match &*string(&entry.RemarkType) {
+            "!Passed" => RemarkKind::Passed,
+            "!Missed" => RemarkKind::Missed,
+            "!Analysis" => RemarkKind::Analysis,
+            "!AnalysisFPCommute" => RemarkKind::AnalysisFPCommute,
+            "!AnalysisAliasing" => RemarkKind::AnalysisAliasing,
+            "!Failure" => RemarkKind::Failure,
+            _ => RemarkKind::Unknown,
+        };
+        let args = if entry.NumArgs == 0 {
+            &[][..]
+        } else {
+            slice::from_raw_parts(entry.Args, entry.NumArgs as usize)
+        };
+        remarks.push(Remark {
+            kind,
+            pass_name: string(&entry.PassName),
+            remark_name: string(&entry.RemarkName),
+            function_name: string(&entry.FunctionName),
+            debug_loc: debug_loc(&entry.DebugLoc),
+            hotness: u64::from(entry.Hotness),
+            args: args
+                .iter()
+                .map(|arg| RemarkArg {
+                    key: string(&arg.Key),
+                    value: string(&arg.Value),
+                    debug_loc: debug_loc(&arg.DebugLoc),
+                })
+                .collect(),
+        });
+    }
+    let result = if LLVMOptRemarkParserHasError(parser) != 0 {
+        Err(CStr::from_ptr(LLVMOptRemarkParserGetErrorMessage(parser))
+            .to_string_lossy()
+            .into_owned())
+    } else {
+        Ok(remarks)
+    };
+    LLVMOptRemarkParserDispose(parser);
+    result
+}
+
+#[cfg(LLVM_VERSION_9_OR_GREATER)]
+unsafe fn parse_remarks_impl(buffer: &[u8]) -> Result<Vec<Remark>, String> {
+    use super::remarks::*;
+
+    unsafe fn string(string: LLVMRemarkStringRef) -> String {
+        if string.is_null() {
+            return String::new();
+        }
+        copy_string(
+            LLVMRemarkStringGetData(string),
+            LLVMRemarkStringGetLen(string),
+        )
+    }
+
+    unsafe fn debug_loc(debug_loc: LLVMRemarkDebugLocRef) -> Option<RemarkDebugLoc> {
+        if debug_loc.is_null() {
+            return None;
+        }
+        Some(RemarkDebugLoc {
+            source_file: string(LLVMRemarkDebugLocGetSourceFilePath(debug_loc)),
+            line: LLVMRemarkDebugLocGetSourceLine(debug_loc),
+            column: LLVMRemarkDebugLocGetSourceColumn(debug_loc),
+        })
+    }
+
+    let parser = LLVMRemarkParserCreateYAML(buffer.as_ptr() as *const _, buffer.len() as u64);
+    let mut remarks = vec![];
+    loop {
+        let entry = LLVMRemarkParserGetNext(parser);
+        if entry.is_null() {
+            break;
+        }
+        let kind = match LLVMRemarkEntryGetType(entry) {
+            LLVMRemarkType::LLVMRemarkTypeUnknown => RemarkKind::Unknown,
+            LLVMRemarkType::LLVMRemarkTypePassed => RemarkKind::Passed,
+            LLVMRemarkType::LLVMRemarkTypeMissed => RemarkKind::Missed,
+            LLVMRemarkType::LLVMRemarkTypeAnalysis => RemarkKind::Analysis,
+            LLVMRemarkType::LLVMRemarkTypeAnalysisFPCommute => RemarkKind::AnalysisFPCommute,
+            LLVMRemarkType::LLVMRemarkTypeAnalysisAliasing => RemarkKind::AnalysisAliasing,
+            LLVMRemarkType::LLVMRemarkTypeFailure => RemarkKind::Failure,
+        };
+        let mut args = vec![];
+        let mut arg = LLVMRemarkEntryGetFirstArg(entry);
+        while !arg.is_null() {
+            args.push(RemarkArg {
+                key: string(LLVMRemarkArgGetKey(arg)),
+                value: string(LLVMRemarkArgGetValue(arg)),
+                debug_loc: debug_loc(LLVMRemarkArgGetDebugLoc(arg)),
+            });
+            arg = LLVMRemarkEntryGetNextArg(arg, entry);
+        }
+        remarks.push(Remark {
+            kind,
+            pass_name: string(LLVMRemarkEntryGetPassName(entry)),
+            remark_name: string(LLVMRemarkEntryGetRemarkName(entry)),
+            function_name: string(LLVMRemarkEntryGetFunctionName(entry)),
+            debug_loc: debug_loc(LLVMRemarkEntryGetDebugLoc(entry)),
+            hotness: LLVMRemarkEntryGetHotness(entry),
+            args,
+        });
+        LLVMRemarkEntryDispose(entry);
+    }
+    let result = if LLVMRemarkParserHasError(parser) != 0 {
+        Err(CStr::from_ptr(LLVMRemarkParserGetErrorMessage(parser))
+            .to_string_lossy()
+            .into_owned())
+    } else {
+        Ok(remarks)
+    };
+    LLVMRemarkParserDispose(parser);
+    result
+}
+
+/// Build a `freeze` of `val`.
+///
+/// Before LLVM 10 there is no `freeze` instruction, and this returns `val`
+/// itself. That is the same for any value that isn't `undef` or poison, but
+/// an `undef` isn't fixed to a single value.
+///
+/// # Safety
+///
+/// `builder` and `val` must be valid, and `name` nul-terminated.
+pub unsafe fn build_freeze(
+    builder: LLVMBuilderRef,
+    val: LLVMValueRef,
+    name: *const ::libc::c_char,
+) -> LLVMValueRef {
+    #[cfg(LLVM_VERSION_10_OR_GREATER)]
+    return LLVMBuildFreeze(builder, val, name);
+
+    #[cfg(LLVM_VERSION_9_OR_LOWER)]
+    {
+        let _ = (builder, name);
+        val
+    }
+}
+
+/// Build an atomic floating-point addition of `val` to the value at `ptr`,
+/// returning the old value, like `atomicrmw fadd`.
+///
+/// Before LLVM 10 there is no `atomicrmw fadd`, and this builds the loop
+/// around a `cmpxchg` that it expands to instead. The builder must then be at
+/// the end of a basic block, and it is left at the end of a new one that
+/// follows the loop. `val` must be a `half`, `float`, `double` or `fp128`.
+///
+/// # Safety
+///
+/// `builder`, `ptr` and `val` must be valid.
+pub unsafe fn build_atomic_fadd(
+    builder: LLVMBuilderRef,
+    ptr: LLVMValueRef,
+    val: LLVMValueRef,
+    ordering: LLVMAtomicOrdering,
+    single_thread: LLVMBool,
+) -> LLVMValueRef {
+    #[cfg(LLVM_VERSION_10_OR_GREATER)]
+    return LLVMBuildAtomicRMW(
+        builder,
+        LLVMAtomicRMWBinOp::LLVMAtomicRMWBinOpFAdd,
+        ptr,
+        val,
+        ordering,
+        single_thread,
+    );
+
+    #[cfg(LLVM_VERSION_9_OR_LOWER)]
+    build_atomic_float_loop(builder, ptr, val, ordering, single_thread, |b, l, r, n| {
+        LLVMBuildFAdd(b, l, r, n)
+    })
+}
+
+/// Build an atomic floating-point subtraction of `val` from the value at
+/// `ptr`, returning the old value, like `atomicrmw fsub`. See
+/// [`build_atomic_fadd`].
+///
+/// # Safety
+///
+/// `builder`, `ptr` and `val` must be valid.
+pub unsafe fn build_atomic_fsub(
+    builder: LLVMBuilderRef,
+    ptr: LLVMValueRef,
+    val: LLVMValueRef,
+    ordering: LLVMAtomicOrdering,
+    single_thread: LLVMBool,
+) -> LLVMValueRef {
+    #[cfg(LLVM_VERSION_10_OR_GREATER)]
+    return LLVMBuildAtomicRMW(
+        builder,
+        LLVMAtomicRMWBinOp::LLVMAtomicRMWBinOpFSub,
+        ptr,
+        val,
+        ordering,
+        single_thread,
+    );
+
+    #[cfg(LLVM_VERSION_9_OR_LOWER)]
+    build_atomic_float_loop(builder, ptr, val, ordering, single_thread, |b, l, r, n| {
+        LLVMBuildFSub(b, l, r, n)
+    })
+}
+
+/// Build the `cmpxchg` loop for a floating-point `atomicrmw`, applying `op` to
+/// the loaded value and `val`, as LLVM's AtomicExpand pass does.
+#[cfg(LLVM_VERSION_9_OR_LOWER)]
+unsafe fn build_atomic_float_loop(
+    builder: LLVMBuilderRef,
+    ptr: LLVMValueRef,
+    val: LLVMValueRef,
+    ordering: LLVMAtomicOrdering,
+    single_thread: LLVMBool,
+    op: unsafe fn(
+        LLVMBuilderRef,
+        LLVMValueRef,
+        LLVMValueRef,
+        *const ::libc::c_char,
+    ) -> LLVMValueRef,
+) -> LLVMValueRef {
+    let no_name = b"\0".as_ptr() as *const ::libc::c_char;
+    let float_type = LLVMTypeOf(val);
+    let context = LLVMGetTypeContext(float_type);
+    let bits = match LLVMGetTypeKind(float_type) {
+        LLVMTypeKind::LLVMHalfTypeKind => 16,
+        LLVMTypeKind::LLVMFloatTypeKind => 32,
+        LLVMTypeKind::LLVMDoubleTypeKind => 64,
+        LLVMTypeKind::LLVMFP128TypeKind => 128,
+        kind => panic!(
+            "Unsupported type for an atomic floating-point operation: {:?}",
+            kind
+        ),
+    };
+    // cmpxchg only takes integers.
+    let int_type = LLVMIntTypeInContext(context, bits);
+    let int_ptr = LLVMBuildBitCast(
+        builder,
+        ptr,
+        LLVMPointerType(int_type, LLVMGetPointerAddressSpace(LLVMTypeOf(ptr))),
+        no_name,
+    );
+    // The failure ordering can't include a release.
+    let failure_ordering = match ordering {
+        LLVMAtomicOrdering::LLVMAtomicOrderingAcquireRelease => {
+            LLVMAtomicOrdering::LLVMAtomicOrderingAcquire
+        }
+        LLVMAtomicOrdering::LLVMAtomicOrderingRelease => {
+            LLVMAtomicOrdering::LLVMAtomicOrderingMonotonic
+        }
+        ordering => ordering,
+    };
+
+    let mut entry = LLVMGetInsertBlock(builder);
+    let function = LLVMGetBasicBlockParent(entry);
+    let mut start = LLVMAppendBasicBlockInContext(
+        context,
+        function,
+        b"atomicrmw.start\0".as_ptr() as *const ::libc::c_char,
+    );
+    let end = LLVMAppendBasicBlockInContext(
+        context,
+        function,
+        b"atomicrmw.end\0".as_ptr() as *const ::libc::c_char,
+    );
+    let mut initial = LLVMBuildLoad(builder, int_ptr, no_name);
+    LLVMBuildBr(builder, start);
+
+    LLVMPositionBuilderAtEnd(builder, start);
+    let loaded = LLVMBuildPhi(
+        builder,
+        int_type,
+        b"loaded\0".as_ptr() as *const ::libc::c_char,
+    );
+    let old = LLVMBuildBitCast(builder, loaded, float_type, no_name);
+    let new = op(
+        builder,
+        old,
+        val,
+        b"new\0".as_ptr() as *const ::libc::c_char,
+    );
+    let new = LLVMBuildBitCast(builder, new, int_type, no_name);
+    let pair = LLVMBuildAtomicCmpXchg(
+        builder,
+        int_ptr,
+        loaded,
+        new,
+        ordering,
+        failure_ordering,
+        single_thread,
+    );
+    let success = LLVMBuildExtractValue(
+        builder,
+        pair,
+        1,
+        b"success\0".as_ptr() as *const ::libc::c_char,
+    );
+    let mut new_loaded = LLVMBuildExtractValue(
+        builder,
+        pair,
+        0,
+        b"newloaded\0".as_ptr() as *const ::libc::c_char,
+    );
+    LLVMBuildCondBr(builder, success, end, start);
+    LLVMAddIncoming(loaded, &mut initial, &mut entry, 1);
+    LLVMAddIncoming(loaded, &mut new_loaded, &mut start, 1);
+
+    LLVMPositionBuilderAtEnd(builder, end);
+    LLVMBuildBitCast(builder, new_loaded, float_type, no_name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::analysis::{LLVMVerifierFailureAction, LLVMVerifyModule};
+    use crate::ir_reader::LLVMParseIRInContext;
+    use crate::target::{LLVM_InitializeNativeAsmPrinter, LLVM_InitializeNativeTarget};
+    use crate::target_machine::*;
+    use std::ptr;
+
+    /// Compile `ir` for the host into an object file.
+    unsafe fn emit_object(context: LLVMContextRef, ir: &str) -> LLVMMemoryBufferRef {
+        assert_eq!(LLVM_InitializeNativeTarget(), 0);
+        assert_eq!(LLVM_InitializeNativeAsmPrinter(), 0);
+        let buffer = LLVMCreateMemoryBufferWithMemoryRangeCopy(
+            ir.as_ptr() as *const _,
+            ir.len(),
+            b"test.ll\0".as_ptr() as *const _,
+        );
+        let mut module = ptr::null_mut();
+        let mut message = ptr::null_mut();
+        assert_eq!(
+            LLVMParseIRInContext(context, buffer, &mut module, &mut message),
+            0
+        );
+
+        let triple = LLVMGetDefaultTargetTriple();
+        let mut target = ptr::null_mut();
+        assert_eq!(
+            LLVMGetTargetFromTriple(triple, &mut target, &mut message),
+            0
+        );
+        let machine = LLVMCreateTargetMachine(
+            target,
+            triple,
+            b"\0".as_ptr() as *const _,
+            b"\0".as_ptr() as *const _,
+            LLVMCodeGenOptLevel::LLVMCodeGenLevelDefault,
+            LLVMRelocMode::LLVMRelocDefault,
+            LLVMCodeModel::LLVMCodeModelDefault,
+        );
+        let mut object = ptr::null_mut();
+        assert_eq!(
+            LLVMTargetMachineEmitToMemoryBuffer(
+                machine,
+                module,
+                LLVMCodeGenFileType::LLVMObjectFile,
+                &mut message,
+                &mut object,
+            ),
+            0
+        );
+        LLVMDisposeTargetMachine(machine);
+        LLVMDisposeMessage(triple);
+        LLVMDisposeModule(module);
+        object
+    }
+
+    /// Copy a string from LLVM, which may be null (the name of ELF's first,
+    /// reserved section is).
+    unsafe fn name(name: *const ::libc::c_char) -> String {
+        if name.is_null() {
+            return String::new();
+        }
+        CStr::from_ptr(name).to_string_lossy().into_owned()
+    }
+
+    #[test]
+    fn object_sections_and_symbols() {
+        unsafe {
+            let context = LLVMContextCreate();
+            let object = emit_object(context, "define i32 @answer() { ret i32 42 }");
+
+            let mut sections = vec![];
+            for_each_section(object, context, |iterator| {
+                sections.push(name(LLVMGetSectionName(iterator)));
+            })
+            .unwrap();
+            // `.text` for ELF and COFF, `__text` for Mach-O.
+            assert!(sections.iter().any(|section| section.ends_with("text")));
+
+            let mut symbols = vec![];
+            for_each_symbol(object, context, |iterator| {
+                symbols.push(name(LLVMGetSymbolName(iterator)));
+            })
+            .unwrap();
+            // `_answer` on Mach-O.
+            assert!(symbols.iter().any(|symbol| symbol.ends_with("answer")));
+
+            // The buffer is still the caller's.
+            LLVMDisposeMemoryBuffer(object);
+            LLVMContextDispose(context);
+        }
+    }
+
+    #[test]
+    fn parse_yaml_remarks() {
+        let yaml = b"--- !Missed
+Pass:            inline
+Name:            NoDefinition
+DebugLoc:        { File: test.c, Line: 3, Column: 10 }
+Function:        caller
+Hotness:         7
+Args:
+  - Callee:          callee
+  - String:          ' will not be inlined into '
+  - Caller:          caller
+    DebugLoc:        { File: test.c, Line: 2, Column: 0 }
+...
+--- !Passed
+Pass:            licm
+Name:            Hoisted
+Function:        caller
+...
+";
+        let remarks = parse_remarks(yaml).unwrap();
+        assert_eq!(remarks.len(), 2);
+
+        let missed = &remarks[0];
+        assert_eq!(missed.kind, RemarkKind::Missed);
+        assert_eq!(missed.pass_name, "inline");
+        assert_eq!(missed.remark_name, "NoDefinition");
+        assert_eq!(missed.function_name, "caller");
+        assert_eq!(
+            missed.debug_loc,
+            Some(RemarkDebugLoc {
+                source_file: String::from("test.c"),
+                line: 3,
+                column: 10,
+            })
+        );
+        assert_eq!(missed.hotness, 7);
+        assert_eq!(missed.args.len(), 3);
+        assert_eq!(missed.args[0].key, "Callee");
+        assert_eq!(missed.args[0].value, "callee");
+        assert_eq!(missed.args[1].value, " will not be inlined into ");
+        assert_eq!(missed.args[0].debug_loc, None);
+        assert_eq!(
+            missed.args[2].debug_loc.as_ref().map(|loc| loc.line),
+            Some(2)
+        );
+
+        assert_eq!(remarks[1].kind, RemarkKind::Passed);
+        assert_eq!(remarks[1].debug_loc, None);
+        assert!(remarks[1].args.is_empty());
+    }
+
+    #[test]
+    fn parse_invalid_remarks() {
+        assert!(parse_remarks(b"--- !Missed\nPass: [\n").is_err());
+    }
+
+    #[test]
+    fn atomic_fadd_verifies() {
+        unsafe {
+            let context = LLVMContextCreate();
+            let module = LLVMModuleCreateWithNameInContext(b"test\0".as_ptr() as *const _, context);
+            let float = LLVMFloatTypeInContext(context);
+            let mut params = [LLVMPointerType(float, 0)];
+            let function_type =
+                LLVMFunctionType(LLVMVoidTypeInContext(context), params.as_mut_ptr(), 1, 0);
+            let function = LLVMAddFunction(module, b"add\0".as_ptr() as *const _, function_type);
+            let entry =
+                LLVMAppendBasicBlockInContext(context, function, b"entry\0".as_ptr() as *const _);
+
+            let builder = LLVMCreateBuilderInContext(context);
+            LLVMPositionBuilderAtEnd(builder, entry);
+            build_atomic_fadd(
+                builder,
+                LLVMGetParam(function, 0),
+                LLVMConstReal(float, 1.0),
+                LLVMAtomicOrdering::LLVMAtomicOrderingSequentiallyConsistent,
+                0,
+            );
+            LLVMBuildRetVoid(builder);
+            LLVMDisposeBuilder(builder);
+
+            let mut message = ptr::null_mut();
+            let failed = LLVMVerifyModule(
+                module,
+                LLVMVerifierFailureAction::LLVMReturnStatusAction,
+                &mut message,
+            );
+            let verifier_message = name(message);
+            LLVMDisposeMessage(message);
+            assert_eq!(failed, 0, "{}", verifier_message);
+
+            let ir = LLVMPrintModuleToString(module);
+            let text = name(ir);
+            LLVMDisposeMessage(ir);
+            #[cfg(LLVM_VERSION_10_OR_GREATER)]
+            assert!(text.contains("atomicrmw fadd"));
+            #[cfg(LLVM_VERSION_9_OR_LOWER)]
+            assert!(text.contains("cmpxchg"));
+
+            LLVMDisposeModule(module);
+            LLVMContextDispose(context);
+        }
+    }
+}
@@ -3,14 +3,14 @@
 use super::*;
 
 // Core
-extern "C" {
+extern_c! {
     pub fn LLVMShutdown();
     pub fn LLVMCreateMessage(Message: *const ::libc::c_char) -> *mut ::libc::c_char;
     pub fn LLVMDisposeMessage(Message: *mut ::libc::c_char);
 }
 
 // Core->Contexts
-extern "C" {
+extern_c! {
     pub fn LLVMContextCreate() -> LLVMContextRef;
     pub fn LLVMGetGlobalContext() -> LLVMContextRef;
     pub fn LLVMContextSetDiagnosticHandler(
@@ -89,7 +89,7 @@ extern "C" {
 }
 
 // Core->Modules
-extern "C" {
+extern_c! {
     pub fn LLVMModuleCreateWithName(ModuleID: *const ::libc::c_char) -> LLVMModuleRef;
     pub fn LLVMModuleCreateWithNameInContext(
         ModuleID: *const ::libc::c_char,
@@ -197,6 +197,7 @@ extern "C" {
         Asm: *const ::libc::c_char,
         Len: ::libc::size_t,
     );
+    #[cfg(LLVM_VERSION_12_OR_LOWER)]
     pub fn LLVMGetInlineAsm(
         Ty: LLVMTypeRef,
         AsmString: *mut ::libc::c_char,
@@ -207,6 +208,18 @@ extern "C" {
         IsAlignStack: LLVMBool,
         Dialect: LLVMInlineAsmDialect,
     ) -> LLVMValueRef;
+    #[cfg(LLVM_VERSION_13_OR_GREATER)]
+    pub fn LLVMGetInlineAsm(
+        Ty: LLVMTypeRef,
+        AsmString: *mut ::libc::c_char,
+        AsmStringSize: ::libc::size_t,
+        Constraints: *mut ::libc::c_char,
+        ConstraintsSize: ::libc::size_t,
+        HasSideEffects: LLVMBool,
+        IsAlignStack: LLVMBool,
+        Dialect: LLVMInlineAsmDialect,
+        CanThrow: LLVMBool,
+    ) -> LLVMValueRef;
 
     pub fn LLVMGetModuleContext(M: LLVMModuleRef) -> LLVMContextRef;
     pub fn LLVMGetTypeByName(M: LLVMModuleRef, Name: *const ::libc::c_char) -> LLVMTypeRef;
@@ -265,7 +278,7 @@ extern "C" {
 }
 
 // Core->Types
-extern "C" {
+extern_c! {
     pub fn LLVMGetTypeKind(Ty: LLVMTypeRef) -> LLVMTypeKind;
     pub fn LLVMTypeIsSized(Ty: LLVMTypeRef) -> LLVMBool;
     pub fn LLVMGetTypeContext(Ty: LLVMTypeRef) -> LLVMContextRef;
@@ -291,12 +304,16 @@ extern "C" {
 
     // Core->Types->Floating-Point
     pub fn LLVMHalfTypeInContext(C: LLVMContextRef) -> LLVMTypeRef;
+    #[cfg(LLVM_VERSION_11_OR_GREATER)]
+    pub fn LLVMBFloatTypeInContext(C: LLVMContextRef) -> LLVMTypeRef;
     pub fn LLVMFloatTypeInContext(C: LLVMContextRef) -> LLVMTypeRef;
     pub fn LLVMDoubleTypeInContext(C: LLVMContextRef) -> LLVMTypeRef;
     pub fn LLVMX86FP80TypeInContext(C: LLVMContextRef) -> LLVMTypeRef;
     pub fn LLVMFP128TypeInContext(C: LLVMContextRef) -> LLVMTypeRef;
     pub fn LLVMPPCFP128TypeInContext(C: LLVMContextRef) -> LLVMTypeRef;
     pub fn LLVMHalfType() -> LLVMTypeRef;
+    #[cfg(LLVM_VERSION_11_OR_GREATER)]
+    pub fn LLVMBFloatType() -> LLVMTypeRef;
     pub fn LLVMFloatType() -> LLVMTypeRef;
     pub fn LLVMDoubleType() -> LLVMTypeRef;
     pub fn LLVMX86FP80Type() -> LLVMTypeRef;
@@ -347,6 +364,10 @@ extern "C" {
     pub fn LLVMIsLiteralStruct(StructTy: LLVMTypeRef) -> LLVMBool;
 
     // Core->Types->Sequential
+    /// Get the element type of an array, vector or pointer type.
+    ///
+    /// From LLVM 15, pointers are opaque by default and have no element
+    /// type, so this works only on array and vector types there.
     pub fn LLVMGetElementType(Ty: LLVMTypeRef) -> LLVMTypeRef;
     /// Get the subtypes of the given type.
     pub fn LLVMGetSubtypes(Tp: LLVMTypeRef, Arr: *mut LLVMTypeRef);
@@ -355,8 +376,23 @@ extern "C" {
     pub fn LLVMArrayType(ElementType: LLVMTypeRef, ElementCount: ::libc::c_uint) -> LLVMTypeRef;
     pub fn LLVMGetArrayLength(ArrayTy: LLVMTypeRef) -> ::libc::c_uint;
     pub fn LLVMPointerType(ElementType: LLVMTypeRef, AddressSpace: ::libc::c_uint) -> LLVMTypeRef;
+    /// Determine whether a pointer is opaque.
+    ///
+    /// True if this is an instance of an opaque PointerType.
+    #[cfg(LLVM_VERSION_15_OR_GREATER)]
+    pub fn LLVMPointerTypeIsOpaque(Ty: LLVMTypeRef) -> LLVMBool;
+    /// Create an opaque pointer type in a context.
+    #[cfg(LLVM_VERSION_15_OR_GREATER)]
+    pub fn LLVMPointerTypeInContext(C: LLVMContextRef, AddressSpace: ::libc::c_uint) -> LLVMTypeRef;
     pub fn LLVMGetPointerAddressSpace(PointerTy: LLVMTypeRef) -> ::libc::c_uint;
     pub fn LLVMVectorType(ElementType: LLVMTypeRef, ElementCount: ::libc::c_uint) -> LLVMTypeRef;
+    /// Create a vector type that contains a defined type and has a scalable
+    /// number of elements.
+    #[cfg(LLVM_VERSION_11_OR_GREATER)]
+    pub fn LLVMScalableVectorType(
+        ElementType: LLVMTypeRef,
+        ElementCount: ::libc::c_uint,
+    ) -> LLVMTypeRef;
     pub fn LLVMGetVectorSize(VectorTy: LLVMTypeRef) -> ::libc::c_uint;
 
     // Core->Types->Other
@@ -371,7 +407,7 @@ extern "C" {
 }
 
 // Core->Values
-extern "C" {
+extern_c! {
     // Core->Values->General
     // Get the enumerated kind of a Value instance.
     pub fn LLVMGetValueKind(Val: LLVMValueRef) -> LLVMValueKind;
@@ -508,24 +544,35 @@ extern "C" {
     pub fn LLVMConstAdd(LHSConstant: LLVMValueRef, RHSConstant: LLVMValueRef) -> LLVMValueRef;
     pub fn LLVMConstNSWAdd(LHSConstant: LLVMValueRef, RHSConstant: LLVMValueRef) -> LLVMValueRef;
     pub fn LLVMConstNUWAdd(LHSConstant: LLVMValueRef, RHSConstant: LLVMValueRef) -> LLVMValueRef;
+    #[cfg(LLVM_VERSION_14_OR_LOWER)]
     pub fn LLVMConstFAdd(LHSConstant: LLVMValueRef, RHSConstant: LLVMValueRef) -> LLVMValueRef;
     pub fn LLVMConstSub(LHSConstant: LLVMValueRef, RHSConstant: LLVMValueRef) -> LLVMValueRef;
     pub fn LLVMConstNSWSub(LHSConstant: LLVMValueRef, RHSConstant: LLVMValueRef) -> LLVMValueRef;
     pub fn LLVMConstNUWSub(LHSConstant: LLVMValueRef, RHSConstant: LLVMValueRef) -> LLVMValueRef;
+    #[cfg(LLVM_VERSION_14_OR_LOWER)]
     pub fn LLVMConstFSub(LHSConstant: LLVMValueRef, RHSConstant: LLVMValueRef) -> LLVMValueRef;
     pub fn LLVMConstMul(LHSConstant: LLVMValueRef, RHSConstant: LLVMValueRef) -> LLVMValueRef;
     pub fn LLVMConstNSWMul(LHSConstant: LLVMValueRef, RHSConstant: LLVMValueRef) -> LLVMValueRef;
     pub fn LLVMConstNUWMul(LHSConstant: LLVMValueRef, RHSConstant: LLVMValueRef) -> LLVMValueRef;
+    #[cfg(LLVM_VERSION_14_OR_LOWER)]
     pub fn LLVMConstFMul(LHSConstant: LLVMValueRef, RHSConstant: LLVMValueRef) -> LLVMValueRef;
+    #[cfg(LLVM_VERSION_14_OR_LOWER)]
     pub fn LLVMConstUDiv(LHSConstant: LLVMValueRef, RHSConstant: LLVMValueRef) -> LLVMValueRef;
+    #[cfg(LLVM_VERSION_14_OR_LOWER)]
     pub fn LLVMConstExactUDiv(LHSConstant: LLVMValueRef, RHSConstant: LLVMValueRef)
         -> LLVMValueRef;
+    #[cfg(LLVM_VERSION_14_OR_LOWER)]
     pub fn LLVMConstSDiv(LHSConstant: LLVMValueRef, RHSConstant: LLVMValueRef) -> LLVMValueRef;
+    #[cfg(LLVM_VERSION_14_OR_LOWER)]
     pub fn LLVMConstExactSDiv(LHSConstant: LLVMValueRef, RHSConstant: LLVMValueRef)
         -> LLVMValueRef;
+    #[cfg(LLVM_VERSION_14_OR_LOWER)]
     pub fn LLVMConstFDiv(LHSConstant: LLVMValueRef, RHSConstant: LLVMValueRef) -> LLVMValueRef;
+    #[cfg(LLVM_VERSION_14_OR_LOWER)]
     pub fn LLVMConstURem(LHSConstant: LLVMValueRef, RHSConstant: LLVMValueRef) -> LLVMValueRef;
+    #[cfg(LLVM_VERSION_14_OR_LOWER)]
     pub fn LLVMConstSRem(LHSConstant: LLVMValueRef, RHSConstant: LLVMValueRef) -> LLVMValueRef;
+    #[cfg(LLVM_VERSION_14_OR_LOWER)]
     pub fn LLVMConstFRem(LHSConstant: LLVMValueRef, RHSConstant: LLVMValueRef) -> LLVMValueRef;
     pub fn LLVMConstAnd(LHSConstant: LLVMValueRef, RHSConstant: LLVMValueRef) -> LLVMValueRef;
     pub fn LLVMConstOr(LHSConstant: LLVMValueRef, RHSConstant: LLVMValueRef) -> LLVMValueRef;
@@ -543,6 +590,13 @@ extern "C" {
     pub fn LLVMConstShl(LHSConstant: LLVMValueRef, RHSConstant: LLVMValueRef) -> LLVMValueRef;
     pub fn LLVMConstLShr(LHSConstant: LLVMValueRef, RHSConstant: LLVMValueRef) -> LLVMValueRef;
     pub fn LLVMConstAShr(LHSConstant: LLVMValueRef, RHSConstant: LLVMValueRef) -> LLVMValueRef;
+    #[cfg_attr(
+        LLVM_VERSION_14_OR_GREATER,
+        deprecated(
+            since = "LLVM 14.0",
+            note = "Use LLVMConstGEP2 instead to support opaque pointers"
+        )
+    )]
     pub fn LLVMConstGEP(
         ConstantVal: LLVMValueRef,
         ConstantIndices: *mut LLVMValueRef,
@@ -554,6 +608,13 @@ extern "C" {
         ConstantIndices: *mut LLVMValueRef,
         NumIndices: ::libc::c_uint,
     ) -> LLVMValueRef;
+    #[cfg_attr(
+        LLVM_VERSION_14_OR_GREATER,
+        deprecated(
+            since = "LLVM 14.0",
+            note = "Use LLVMConstInBoundsGEP2 instead to support opaque pointers"
+        )
+    )]
     pub fn LLVMConstInBoundsGEP(
         ConstantVal: LLVMValueRef,
         ConstantIndices: *mut LLVMValueRef,
@@ -607,11 +668,13 @@ extern "C" {
         VectorBConstant: LLVMValueRef,
         MaskConstant: LLVMValueRef,
     ) -> LLVMValueRef;
+    #[cfg(LLVM_VERSION_14_OR_LOWER)]
     pub fn LLVMConstExtractValue(
         AggConstant: LLVMValueRef,
         IdxList: *mut ::libc::c_uint,
         NumIdx: ::libc::c_uint,
     ) -> LLVMValueRef;
+    #[cfg(LLVM_VERSION_14_OR_LOWER)]
     pub fn LLVMConstInsertValue(
         AggConstant: LLVMValueRef,
         ElementValueConstant: LLVMValueRef,
@@ -723,12 +786,35 @@ extern "C" {
     /// Set the target value of an alias.
     pub fn LLVMAliasSetAliasee(Alias: LLVMValueRef, Aliasee: LLVMValueRef);
 
+    #[cfg_attr(
+
+        LLVM_VERSION_14_OR_GREATER,
+
+        deprecated(
+
+            since = "LLVM 14.0",
+
+            note = "Use LLVMAddAlias2 instead to support opaque pointers"
+
+        )
+
+    )]
     pub fn LLVMAddAlias(
         M: LLVMModuleRef,
         Ty: LLVMTypeRef,
         Aliasee: LLVMValueRef,
         Name: *const ::libc::c_char,
     ) -> LLVMValueRef;
+    /// Add a GlobalAlias with the given value type, address space and
+    /// aliasee.
+    #[cfg(LLVM_VERSION_14_OR_GREATER)]
+    pub fn LLVMAddAlias2(
+        M: LLVMModuleRef,
+        ValueTy: LLVMTypeRef,
+        AddrSpace: ::libc::c_uint,
+        Aliasee: LLVMValueRef,
+        Name: *const ::libc::c_char,
+    ) -> LLVMValueRef;
 
     // ..->Function Values
     pub fn LLVMDeleteFunction(Fn: LLVMValueRef);
@@ -758,6 +844,7 @@ extern "C" {
     ) -> LLVMValueRef;
     pub fn LLVMIntrinsicGetType(
         Ctx: LLVMContextRef,
+        ID: ::libc::c_uint,
         ParamTypes: *mut LLVMTypeRef,
         ParamCount: ::libc::size_t,
     ) -> LLVMTypeRef;
@@ -825,7 +912,7 @@ extern "C" {
 }
 
 // Core->Metadata
-extern "C" {
+extern_c! {
     #[cfg_attr(LLVM_VERSION_9_OR_GREATER, deprecated(since = "LLVM 9.0", note = "Use LLVMMDStringInContext2 instead."))]
     pub fn LLVMMDStringInContext(
         C: LLVMContextRef,
@@ -924,7 +1011,7 @@ extern "C" {
 }
 
 // Core->Basic Block
-extern "C" {
+extern_c! {
     pub fn LLVMBasicBlockAsValue(BB: LLVMBasicBlockRef) -> LLVMValueRef;
     pub fn LLVMValueIsBasicBlock(Val: LLVMValueRef) -> LLVMBool;
     pub fn LLVMValueAsBasicBlock(Val: LLVMValueRef) -> LLVMBasicBlockRef;
@@ -977,7 +1064,7 @@ extern "C" {
 }
 
 // Core->Instructions
-extern "C" {
+extern_c! {
     pub fn LLVMHasMetadata(Val: LLVMValueRef) -> ::libc::c_int;
     pub fn LLVMGetMetadata(Val: LLVMValueRef, KindID: ::libc::c_uint) -> LLVMValueRef;
     pub fn LLVMSetMetadata(Val: LLVMValueRef, KindID: ::libc::c_uint, Node: LLVMValueRef);
@@ -1079,6 +1166,9 @@ extern "C" {
     pub fn LLVMIsInBounds(GEP: LLVMValueRef) -> LLVMBool;
     /// Set the given GEP instruction to be inbounds or not.
     pub fn LLVMSetIsInBounds(GEP: LLVMValueRef, InBounds: LLVMBool);
+    /// Get the source element type of the given GEP operator.
+    #[cfg(LLVM_VERSION_14_OR_GREATER)]
+    pub fn LLVMGetGEPSourceElementType(GEP: LLVMValueRef) -> LLVMTypeRef;
 
     // Instruction->PHI Nodes
     pub fn LLVMAddIncoming(
@@ -1094,7 +1184,7 @@ extern "C" {
 }
 
 // Core->Values again; these don't appear in Doxygen because they're macro-generated.
-extern "C" {
+extern_c! {
     pub fn LLVMIsAArgument(Val: LLVMValueRef) -> LLVMValueRef;
     pub fn LLVMIsABasicBlock(Val: LLVMValueRef) -> LLVMValueRef;
     pub fn LLVMIsAInlineAsm(Val: LLVMValueRef) -> LLVMValueRef;
@@ -1192,14 +1282,14 @@ extern "C" {
 }
 
 // Core->Extract/Insert Value
-extern "C" {
+extern_c! {
     /// Get the number of indices on an ExtractValue, InsertValue or GEP instruction.
     pub fn LLVMGetNumIndices(Inst: LLVMValueRef) -> ::libc::c_uint;
     pub fn LLVMGetIndices(Inst: LLVMValueRef) -> *const ::libc::c_uint;
 }
 
 // Core->Instruction Builders
-extern "C" {
+extern_c! {
     pub fn LLVMCreateBuilderInContext(C: LLVMContextRef) -> LLVMBuilderRef;
     pub fn LLVMCreateBuilder() -> LLVMBuilderRef;
     pub fn LLVMPositionBuilder(
@@ -1269,6 +1359,13 @@ extern "C" {
         Addr: LLVMValueRef,
         NumDests: ::libc::c_uint,
     ) -> LLVMValueRef;
+    #[cfg_attr(
+        LLVM_VERSION_14_OR_GREATER,
+        deprecated(
+            since = "LLVM 14.0",
+            note = "Use LLVMBuildInvoke2 instead to support opaque pointers"
+        )
+    )]
     pub fn LLVMBuildInvoke(
         arg1: LLVMBuilderRef,
         Fn: LLVMValueRef,
@@ -1615,6 +1712,13 @@ extern "C" {
         Name: *const ::libc::c_char,
     ) -> LLVMValueRef;
     pub fn LLVMBuildFree(arg1: LLVMBuilderRef, PointerVal: LLVMValueRef) -> LLVMValueRef;
+    #[cfg_attr(
+        LLVM_VERSION_14_OR_GREATER,
+        deprecated(
+            since = "LLVM 14.0",
+            note = "Use LLVMBuildLoad2 instead to support opaque pointers"
+        )
+    )]
     pub fn LLVMBuildLoad(
         arg1: LLVMBuilderRef,
         PointerVal: LLVMValueRef,
@@ -1631,6 +1735,13 @@ extern "C" {
         Val: LLVMValueRef,
         Ptr: LLVMValueRef,
     ) -> LLVMValueRef;
+    #[cfg_attr(
+        LLVM_VERSION_14_OR_GREATER,
+        deprecated(
+            since = "LLVM 14.0",
+            note = "Use LLVMBuildGEP2 instead to support opaque pointers"
+        )
+    )]
     pub fn LLVMBuildGEP(
         B: LLVMBuilderRef,
         Pointer: LLVMValueRef,
@@ -1638,6 +1749,13 @@ extern "C" {
         NumIndices: ::libc::c_uint,
         Name: *const ::libc::c_char,
     ) -> LLVMValueRef;
+    #[cfg_attr(
+        LLVM_VERSION_14_OR_GREATER,
+        deprecated(
+            since = "LLVM 14.0",
+            note = "Use LLVMBuildInBoundsGEP2 instead to support opaque pointers"
+        )
+    )]
     pub fn LLVMBuildInBoundsGEP(
         B: LLVMBuilderRef,
         Pointer: LLVMValueRef,
@@ -1645,6 +1763,13 @@ extern "C" {
         NumIndices: ::libc::c_uint,
         Name: *const ::libc::c_char,
     ) -> LLVMValueRef;
+    #[cfg_attr(
+        LLVM_VERSION_14_OR_GREATER,
+        deprecated(
+            since = "LLVM 14.0",
+            note = "Use LLVMBuildStructGEP2 instead to support opaque pointers"
+        )
+    )]
     pub fn LLVMBuildStructGEP(
         B: LLVMBuilderRef,
         Pointer: LLVMValueRef,
@@ -1849,6 +1974,13 @@ extern "C" {
         Ty: LLVMTypeRef,
         Name: *const ::libc::c_char,
     ) -> LLVMValueRef;
+    #[cfg_attr(
+        LLVM_VERSION_14_OR_GREATER,
+        deprecated(
+            since = "LLVM 14.0",
+            note = "Use LLVMBuildCall2 instead to support opaque pointers"
+        )
+    )]
     pub fn LLVMBuildCall(
         arg1: LLVMBuilderRef,
         Fn: LLVMValueRef,
@@ -1926,12 +2058,27 @@ extern "C" {
         Val: LLVMValueRef,
         Name: *const ::libc::c_char,
     ) -> LLVMValueRef;
+    #[cfg_attr(
+        LLVM_VERSION_14_OR_GREATER,
+        deprecated(
+            since = "LLVM 14.0",
+            note = "Use LLVMBuildPtrDiff2 instead to support opaque pointers"
+        )
+    )]
     pub fn LLVMBuildPtrDiff(
         arg1: LLVMBuilderRef,
         LHS: LLVMValueRef,
         RHS: LLVMValueRef,
         Name: *const ::libc::c_char,
     ) -> LLVMValueRef;
+    #[cfg(LLVM_VERSION_14_OR_GREATER)]
+    pub fn LLVMBuildPtrDiff2(
+        arg1: LLVMBuilderRef,
+        ElemTy: LLVMTypeRef,
+        LHS: LLVMValueRef,
+        RHS: LLVMValueRef,
+        Name: *const ::libc::c_char,
+    ) -> LLVMValueRef;
     pub fn LLVMBuildFence(
         B: LLVMBuilderRef,
         ordering: LLVMAtomicOrdering,
@@ -1961,16 +2108,31 @@ extern "C" {
     pub fn LLVMSetCmpXchgSuccessOrdering(CmpXchgInst: LLVMValueRef, Ordering: LLVMAtomicOrdering);
     pub fn LLVMGetCmpXchgFailureOrdering(CmpXchgInst: LLVMValueRef) -> LLVMAtomicOrdering;
     pub fn LLVMSetCmpXchgFailureOrdering(CmpXchgInst: LLVMValueRef, Ordering: LLVMAtomicOrdering);
+
+    /// Get the number of elements in the mask of a ShuffleVector instruction.
+    #[cfg(LLVM_VERSION_11_OR_GREATER)]
+    pub fn LLVMGetNumMaskElements(ShuffleVectorInst: LLVMValueRef) -> ::libc::c_uint;
+    /// Returns a constant that specifies that the result of a ShuffleVectorInst
+    /// is undefined.
+    #[cfg(LLVM_VERSION_11_OR_GREATER)]
+    pub fn LLVMGetUndefMaskElem() -> ::libc::c_int;
+    /// Get the mask value at position Elt in the mask of a ShuffleVector
+    /// instruction.
+    ///
+    /// Returns the result of LLVMGetUndefMaskElem() if the mask value is undef
+    /// at that position.
+    #[cfg(LLVM_VERSION_11_OR_GREATER)]
+    pub fn LLVMGetMaskValue(ShuffleVectorInst: LLVMValueRef, Elt: ::libc::c_uint) -> ::libc::c_int;
 }
 
 // Core->Module Providers
-extern "C" {
+extern_c! {
     pub fn LLVMCreateModuleProviderForExistingModule(M: LLVMModuleRef) -> LLVMModuleProviderRef;
     pub fn LLVMDisposeModuleProvider(M: LLVMModuleProviderRef);
 }
 
 // Core->Memory Buffers
-extern "C" {
+extern_c! {
     pub fn LLVMCreateMemoryBufferWithContentsOfFile(
         Path: *const ::libc::c_char,
         OutMemBuf: *mut LLVMMemoryBufferRef,
@@ -1997,12 +2159,12 @@ extern "C" {
 }
 
 // Core->pass registry
-extern "C" {
+extern_c! {
     pub fn LLVMGetGlobalPassRegistry() -> LLVMPassRegistryRef;
 }
 
 // Core->Pass managers
-extern "C" {
+extern_c! {
     pub fn LLVMCreatePassManager() -> LLVMPassManagerRef;
     pub fn LLVMCreateFunctionPassManagerForModule(M: LLVMModuleRef) -> LLVMPassManagerRef;
     pub fn LLVMCreateFunctionPassManager(MP: LLVMModuleProviderRef) -> LLVMPassManagerRef;
@@ -2014,7 +2176,7 @@ extern "C" {
 }
 
 // Core->Threading
-extern "C" {
+extern_c! {
     /// Deprecated: LLVM threading is configured at compile-time with `LLVM_ENABLE_THREADS`
     pub fn LLVMStartMultithreaded() -> LLVMBool;
     /// Deprecated: LLVM threading is configured at compile-time with `LLVM_ENABLE_THREADS`
@@ -141,6 +141,12 @@ pub enum LLVMMetadataKind {
     LLVMDIMacroFileMetadataKind,
     #[cfg(LLVM_VERSION_9_OR_GREATER)]
     LLVMDICommonBlockMetadataKind,
+    #[cfg(LLVM_VERSION_11_OR_GREATER)]
+    LLVMDIStringTypeMetadataKind,
+    #[cfg(LLVM_VERSION_12_OR_GREATER)]
+    LLVMDIGenericSubrangeMetadataKind,
+    #[cfg(LLVM_VERSION_13_OR_GREATER)]
+    LLVMDIArgListMetadataKind,
 }
 
 pub type LLVMDWARFTypeEncoding = ::libc::c_uint;
@@ -156,7 +162,7 @@ pub enum LLVMDWARFMacinfoRecordType {
     LLVMDWARFMacinfoRecordTypeVendorExt = 0xff,
 }
 
-extern "C" {
+extern_c! {
     /// The current debug metadata version number.
     pub fn LLVMDebugMetadataVersion() -> ::libc::c_uint;
     /// The version of debug metadata that's present in the provided Module.
@@ -173,6 +179,33 @@ extern "C" {
     pub fn LLVMDisposeDIBuilder(Builder: LLVMDIBuilderRef);
     /// Construct any deferred debug info descriptors.
     pub fn LLVMDIBuilderFinalize(Builder: LLVMDIBuilderRef);
+    /// Finalize a specific subprogram.
+    ///
+    /// No new variables may be added to this subprogram afterwards.
+    #[cfg(LLVM_VERSION_11_OR_GREATER)]
+    pub fn LLVMDIBuilderFinalizeSubprogram(
+        Builder: LLVMDIBuilderRef,
+        Subprogram: LLVMMetadataRef,
+    );
+    #[cfg(LLVM_VERSION_10_OR_LOWER)]
+    pub fn LLVMDIBuilderCreateCompileUnit(
+        Builder: LLVMDIBuilderRef,
+        Lang: LLVMDWARFSourceLanguage,
+        FileRef: LLVMMetadataRef,
+        Producer: *const ::libc::c_char,
+        ProducerLen: ::libc::size_t,
+        isOptimized: LLVMBool,
+        Flags: *const ::libc::c_char,
+        FlagsLen: ::libc::size_t,
+        RuntimeVer: ::libc::c_uint,
+        SplitName: *const ::libc::c_char,
+        SplitNameLen: ::libc::size_t,
+        Kind: LLVMDWARFEmissionKind,
+        DWOId: ::libc::c_uint,
+        SplitDebugInlining: LLVMBool,
+        DebugInfoForProfiling: LLVMBool,
+    ) -> LLVMMetadataRef;
+    #[cfg(LLVM_VERSION_11_OR_GREATER)]
     pub fn LLVMDIBuilderCreateCompileUnit(
         Builder: LLVMDIBuilderRef,
         Lang: LLVMDWARFSourceLanguage,
@@ -189,6 +222,10 @@ extern "C" {
         DWOId: ::libc::c_uint,
         SplitDebugInlining: LLVMBool,
         DebugInfoForProfiling: LLVMBool,
+        SysRoot: *const ::libc::c_char,
+        SysRootLen: ::libc::size_t,
+        SDK: *const ::libc::c_char,
+        SDKLen: ::libc::size_t,
     ) -> LLVMMetadataRef;
     /// Create a file descriptor to hold debugging information for a file.
     pub fn LLVMDIBuilderCreateFile(
@@ -200,6 +237,7 @@ extern "C" {
     ) -> LLVMMetadataRef;
 
     /// Creates a new descriptor for a module with the specified parent scope.
+    #[cfg(LLVM_VERSION_9_OR_LOWER)]
     pub fn LLVMDIBuilderCreateModule(
         Builder: LLVMDIBuilderRef,
         ParentScope: LLVMMetadataRef,
@@ -209,15 +247,37 @@ extern "C" {
         ConfigMacrosLen: ::libc::size_t,
         IncludePath: *const ::libc::c_char,
         IncludePathLen: ::libc::size_t,
-        #[cfg(LLVM_VERSION_9_OR_LOWER)]
         ISysRoot: *const ::libc::c_char,
-        #[cfg(LLVM_VERSION_10_OR_GREATER)]
-        SysRoot: *const ::libc::c_char,
-        #[cfg(LLVM_VERSION_9_OR_LOWER)]
         ISysRootLen: ::libc::size_t,
-        #[cfg(LLVM_VERSION_10_OR_GREATER)]
+    ) -> LLVMMetadataRef;
+    /// Creates a new descriptor for a module with the specified parent scope.
+    #[cfg(all(LLVM_VERSION_10_OR_GREATER, LLVM_VERSION_10_OR_LOWER))]
+    pub fn LLVMDIBuilderCreateModule(
+        Builder: LLVMDIBuilderRef,
+        ParentScope: LLVMMetadataRef,
+        Name: *const ::libc::c_char,
+        NameLen: ::libc::size_t,
+        ConfigMacros: *const ::libc::c_char,
+        ConfigMacrosLen: ::libc::size_t,
+        IncludePath: *const ::libc::c_char,
+        IncludePathLen: ::libc::size_t,
+        SysRoot: *const ::libc::c_char,
         SysRootLen: ::libc::size_t,
     ) -> LLVMMetadataRef;
+    /// Creates a new descriptor for a module with the specified parent scope.
+    #[cfg(LLVM_VERSION_11_OR_GREATER)]
+    pub fn LLVMDIBuilderCreateModule(
+        Builder: LLVMDIBuilderRef,
+        ParentScope: LLVMMetadataRef,
+        Name: *const ::libc::c_char,
+        NameLen: ::libc::size_t,
+        ConfigMacros: *const ::libc::c_char,
+        ConfigMacrosLen: ::libc::size_t,
+        IncludePath: *const ::libc::c_char,
+        IncludePathLen: ::libc::size_t,
+        APINotesFile: *const ::libc::c_char,
+        APINotesFileLen: ::libc::size_t,
+    ) -> LLVMMetadataRef;
 
     /// Creates a new descriptor for a namespace with the specified parent scope.
     pub fn LLVMDIBuilderCreateNameSpace(
@@ -273,6 +333,7 @@ extern "C" {
     ) -> LLVMMetadataRef;
 
     /// Create a descriptor for an imported module that aliases another imported entity descriptor.
+    #[cfg(LLVM_VERSION_13_OR_LOWER)]
     pub fn LLVMDIBuilderCreateImportedModuleFromAlias(
         Builder: LLVMDIBuilderRef,
         Scope: LLVMMetadataRef,
@@ -280,8 +341,19 @@ extern "C" {
         File: LLVMMetadataRef,
         Line: ::libc::c_uint,
     ) -> LLVMMetadataRef;
+    #[cfg(LLVM_VERSION_14_OR_GREATER)]
+    pub fn LLVMDIBuilderCreateImportedModuleFromAlias(
+        Builder: LLVMDIBuilderRef,
+        Scope: LLVMMetadataRef,
+        ImportedEntity: LLVMMetadataRef,
+        File: LLVMMetadataRef,
+        Line: ::libc::c_uint,
+        Elements: *mut LLVMMetadataRef,
+        NumElements: ::libc::c_uint,
+    ) -> LLVMMetadataRef;
 
     /// Create a descriptor for an imported module.
+    #[cfg(LLVM_VERSION_13_OR_LOWER)]
     pub fn LLVMDIBuilderCreateImportedModuleFromModule(
         Builder: LLVMDIBuilderRef,
         Scope: LLVMMetadataRef,
@@ -289,10 +361,21 @@ extern "C" {
         File: LLVMMetadataRef,
         Line: ::libc::c_uint,
     ) -> LLVMMetadataRef;
+    #[cfg(LLVM_VERSION_14_OR_GREATER)]
+    pub fn LLVMDIBuilderCreateImportedModuleFromModule(
+        Builder: LLVMDIBuilderRef,
+        Scope: LLVMMetadataRef,
+        M: LLVMMetadataRef,
+        File: LLVMMetadataRef,
+        Line: ::libc::c_uint,
+        Elements: *mut LLVMMetadataRef,
+        NumElements: ::libc::c_uint,
+    ) -> LLVMMetadataRef;
 
     /// Create a descriptor for an imported function, type, or variable.
     ///
     /// Suitable for e.g. FORTRAN-style USE declarations.
+    #[cfg(LLVM_VERSION_13_OR_LOWER)]
     pub fn LLVMDIBuilderCreateImportedDeclaration(
         Builder: LLVMDIBuilderRef,
         Scope: LLVMMetadataRef,
@@ -302,6 +385,18 @@ extern "C" {
         Name: *const ::libc::c_char,
         NameLen: ::libc::size_t,
     ) -> LLVMMetadataRef;
+    #[cfg(LLVM_VERSION_14_OR_GREATER)]
+    pub fn LLVMDIBuilderCreateImportedDeclaration(
+        Builder: LLVMDIBuilderRef,
+        Scope: LLVMMetadataRef,
+        Decl: LLVMMetadataRef,
+        File: LLVMMetadataRef,
+        Line: ::libc::c_uint,
+        Name: *const ::libc::c_char,
+        NameLen: ::libc::size_t,
+        Elements: *mut LLVMMetadataRef,
+        NumElements: ::libc::c_uint,
+    ) -> LLVMMetadataRef;
 
     /// Creates a new DebugLocation that describes a source location.
     pub fn LLVMDIBuilderCreateDebugLocation(
@@ -590,6 +685,18 @@ extern "C" {
     pub fn LLVMDIBuilderCreateNullPtrType(Builder: LLVMDIBuilderRef) -> LLVMMetadataRef;
 
     /// Create debugging information entry for a typedef.
+    #[cfg(LLVM_VERSION_9_OR_LOWER)]
+    pub fn LLVMDIBuilderCreateTypedef(
+        Builder: LLVMDIBuilderRef,
+        Type: LLVMMetadataRef,
+        Name: *const ::libc::c_char,
+        NameLen: ::libc::size_t,
+        File: LLVMMetadataRef,
+        LineNo: ::libc::c_uint,
+        Scope: LLVMMetadataRef,
+    ) -> LLVMMetadataRef;
+    /// Create debugging information entry for a typedef.
+    #[cfg(LLVM_VERSION_10_OR_GREATER)]
     pub fn LLVMDIBuilderCreateTypedef(
         Builder: LLVMDIBuilderRef,
         Type: LLVMMetadataRef,
@@ -598,7 +705,6 @@ extern "C" {
         File: LLVMMetadataRef,
         LineNo: ::libc::c_uint,
         Scope: LLVMMetadataRef,
-        #[cfg(LLVM_VERSION_10_OR_GREATER)]
         AlignInBits: u32,
     ) -> LLVMMetadataRef;
 
@@ -108,7 +108,7 @@ pub type LLVMSymbolLookupCallback = Option<
     ) -> *const ::libc::c_char,
 >;
 
-extern "C" {
+extern_c! {
     pub fn LLVMCreateDisasm(
         TripleName: *const ::libc::c_char,
         DisInfo: *mut ::libc::c_void,
@@ -7,10 +7,10 @@ pub type LLVMErrorRef = *mut LLVMOpaqueError;
 
 pub type LLVMErrorTypeId = *const ::libc::c_void;
 
-extern "C" {
-    pub fn LLVMGetErrorTypeId(Err: LLVMErrorRef) -> LLVMErrorTypeId;
-    pub fn LLVMConsumeError(Err: LLVMErrorRef);
-    pub fn LLVMGetErrorMessage(Err: LLVMErrorRef) -> *mut ::libc::c_char;
+extern_c! {
+    pub fn LLVMGetErrorTypeId(Error: LLVMErrorRef) -> LLVMErrorTypeId;
+    pub fn LLVMConsumeError(Error: LLVMErrorRef);
+    pub fn LLVMGetErrorMessage(Error: LLVMErrorRef) -> *mut ::libc::c_char;
     pub fn LLVMDisposeErrorMessage(ErrMsg: *mut ::libc::c_char);
     pub fn LLVMGetStringErrorTypeId() -> LLVMErrorTypeId;
 }
@@ -1,6 +1,6 @@
 pub type LLVMFatalErrorHandler = Option<extern "C" fn(Reason: *const ::libc::c_char)>;
 
-extern "C" {
+extern_c! {
     /// Install a fatal error handler.
     ///
     /// LLVM will call `exit(1)` if it detects a fatal error. A callback
@@ -47,8 +47,10 @@ pub type LLVMMemoryManagerFinalizeMemoryCallback =
     extern "C" fn(Opaque: *mut ::libc::c_void, ErrMsg: *mut *mut ::libc::c_char) -> LLVMBool;
 pub type LLVMMemoryManagerDestroyCallback = Option<extern "C" fn(Opaque: *mut ::libc::c_void)>;
 
-extern "C" {
+extern_c! {
+    #[cfg(LLVM_COMPONENT_MCJIT)]
     pub fn LLVMLinkInMCJIT();
+    #[cfg(LLVM_COMPONENT_INTERPRETER)]
     pub fn LLVMLinkInInterpreter();
 
     // Operations on generic values
@@ -80,17 +82,20 @@ extern "C" {
         M: LLVMModuleRef,
         OutError: *mut *mut ::libc::c_char,
     ) -> LLVMBool;
+    #[cfg(LLVM_COMPONENT_INTERPRETER)]
     pub fn LLVMCreateInterpreterForModule(
         OutInterp: *mut LLVMExecutionEngineRef,
         M: LLVMModuleRef,
         OutError: *mut *mut ::libc::c_char,
     ) -> LLVMBool;
+    #[cfg(LLVM_COMPONENT_MCJIT)]
     pub fn LLVMCreateJITCompilerForModule(
         OutJIT: *mut LLVMExecutionEngineRef,
         M: LLVMModuleRef,
         OptLevel: ::libc::c_uint,
         OutError: *mut *mut ::libc::c_char,
     ) -> LLVMBool;
+    #[cfg(LLVM_COMPONENT_MCJIT)]
     pub fn LLVMInitializeMCJITCompilerOptions(
         Options: *mut LLVMMCJITCompilerOptions,
         SizeOfOptions: ::libc::size_t,
@@ -119,6 +124,7 @@ extern "C" {
     /// ```
     ///
     /// 0 is returned on success, or 1 on failure.
+    #[cfg(LLVM_COMPONENT_MCJIT)]
     pub fn LLVMCreateMCJITCompilerForModule(
         OutJIT: *mut LLVMExecutionEngineRef,
         M: LLVMModuleRef,
@@ -176,6 +182,14 @@ extern "C" {
         Name: *const ::libc::c_char,
     ) -> u64;
     pub fn LLVMGetFunctionAddress(EE: LLVMExecutionEngineRef, Name: *const ::libc::c_char) -> u64;
+    /// Returns true on error, false on success. If true is returned then the
+    /// error message is copied to OutError and cleared in the ExecutionEngine
+    /// instance.
+    #[cfg(LLVM_VERSION_11_OR_GREATER)]
+    pub fn LLVMExecutionEngineGetErrMsg(
+        EE: LLVMExecutionEngineRef,
+        OutError: *mut *mut ::libc::c_char,
+    ) -> LLVMBool;
 
     // Operations on memory managers
     // Create a simple custom MCJIT memory manager.
@@ -2,7 +2,7 @@
 
 use super::prelude::*;
 
-extern "C" {
+extern_c! {
     pub fn LLVMInitializeCore(R: LLVMPassRegistryRef);
     pub fn LLVMInitializeTransformUtils(R: LLVMPassRegistryRef);
     pub fn LLVMInitializeScalarOpts(R: LLVMPassRegistryRef);
@@ -10,6 +10,7 @@ extern "C" {
     pub fn LLVMInitializeVectorization(R: LLVMPassRegistryRef);
     pub fn LLVMInitializeInstCombine(R: LLVMPassRegistryRef);
     pub fn LLVMInitializeAggressiveInstCombiner(R: LLVMPassRegistryRef);
+    #[cfg(LLVM_COMPONENT_IPO)]
     pub fn LLVMInitializeIPO(R: LLVMPassRegistryRef);
     pub fn LLVMInitializeInstrumentation(R: LLVMPassRegistryRef);
     pub fn LLVMInitializeAnalysis(R: LLVMPassRegistryRef);
@@ -2,7 +2,7 @@
 
 use super::prelude::*;
 
-extern "C" {
+extern_c! {
     /// Read LLVM IR from a memory buffer and convert it to an in-memory Module.
     ///
     /// Returns 0 on success, and an optional human-readable description of any
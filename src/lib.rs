@@ -5,9 +5,38 @@
 
 #![allow(non_upper_case_globals)]
 #![allow(non_camel_case_types)]
+// With runtime-loading, the functions (and their parameters) are defined here
+// instead of declared, which brings the usual lints for functions with them
+// (and for deprecating them "since" an LLVM version).
+#![cfg_attr(
+    LLVM_SYS_RUNTIME_LOADING,
+    allow(
+        non_snake_case,
+        clippy::deprecated_semver,
+        clippy::missing_safety_doc,
+        clippy::too_many_arguments
+    )
+)]
 
 extern crate libc;
 
+// With the bindgen feature, the build script generates the bindings from the
+// LLVM headers instead of using the ones below, with the same modules and
+// prelude.
+#[cfg(LLVM_SYS_BINDGEN)]
+include!(concat!(env!("OUT_DIR"), "/bindings.rs"));
+
+// Expands to the items it is given, so that one cfg can leave out all of the
+// hand-written bindings.
+#[cfg(not(LLVM_SYS_BINDGEN))]
+macro_rules! handwritten {
+    ($($item:item)*) => {
+        $($item)*
+    };
+}
+
+#[cfg(not(LLVM_SYS_BINDGEN))]
+handwritten! {
 use self::prelude::*;
 
 #[derive(Debug)]
@@ -97,36 +126,77 @@ pub mod prelude {
     pub type LLVMAttributeRef = *mut super::LLVMOpaqueAttributeRef;
 }
 
+/// Declares the functions of the C API in the modules below. Normally this is
+/// just an `extern "C"` block; with the runtime-loading feature, each function
+/// is instead a shim with the same signature that calls through the table
+/// filled in by `runtime::load`.
+#[cfg(not(LLVM_SYS_RUNTIME_LOADING))]
+macro_rules! extern_c {
+    ($($(#[$attr:meta])* pub fn $name:ident($($arg:ident: $ty:ty),* $(,)?) $(-> $ret:ty)?;)*) => {
+        extern "C" {
+            $($(#[$attr])* pub fn $name($($arg: $ty),*) $(-> $ret)?;)*
+        }
+    };
+}
+
+#[cfg(LLVM_SYS_RUNTIME_LOADING)]
+macro_rules! extern_c {
+    ($($(#[$attr:meta])* pub fn $name:ident($($arg:ident: $ty:ty),* $(,)?) $(-> $ret:ty)?;)*) => {
+        $(
+            $(#[$attr])*
+            pub unsafe fn $name($($arg: $ty),*) $(-> $ret)? {
+                static FUNCTION: crate::runtime::Function =
+                    crate::runtime::Function::new(concat!(stringify!($name), "\0"));
+                let function = ::std::mem::transmute::<
+                    *const ::libc::c_void,
+                    unsafe extern "C" fn($($ty),*) $(-> $ret)?,
+                >(FUNCTION.address());
+                function($($arg),*)
+            }
+        )*
+    };
+}
+
 pub mod analysis;
 pub mod bit_reader;
 pub mod bit_writer;
 pub mod comdat;
+pub mod compat;
 pub mod core;
 pub mod debuginfo;
 pub mod disassembler;
 pub mod error;
 pub mod error_handling;
+#[cfg(any(LLVM_COMPONENT_MCJIT, LLVM_COMPONENT_INTERPRETER))]
 pub mod execution_engine;
 pub mod initialization;
 pub mod ir_reader;
+#[cfg(all(LLVM_COMPONENT_LTO, LLVM_VERSION_11_OR_LOWER))]
 pub mod link_time_optimizer;
 pub mod linker;
+#[cfg(LLVM_COMPONENT_LTO)]
 pub mod lto;
 pub mod object;
+#[cfg(LLVM_COMPONENT_ORC)]
 pub mod orc;
 #[cfg(LLVM_VERSION_8_OR_LOWER)]
 pub mod opt_remarks;
 #[cfg(LLVM_VERSION_9_OR_GREATER)]
 pub mod remarks;
+#[cfg(feature = "safe")]
+pub mod safe;
 pub mod support;
 pub mod target;
 pub mod target_machine;
 
 pub mod transforms {
     pub mod aggressive_instcombine;
+    #[cfg(LLVM_COMPONENT_COROUTINES)]
     pub mod coroutines;
     pub mod instcombine;
+    #[cfg(LLVM_COMPONENT_IPO)]
     pub mod ipo;
+    #[cfg(LLVM_COMPONENT_IPO)]
     pub mod pass_manager_builder;
     pub mod scalar;
     pub mod util;
@@ -227,6 +297,12 @@ pub enum LLVMTypeKind {
     LLVMMetadataTypeKind = 14,
     LLVMX86_MMXTypeKind = 15,
     LLVMTokenTypeKind = 16,
+    #[cfg(LLVM_VERSION_11_OR_GREATER)]
+    LLVMScalableVectorTypeKind = 17,
+    #[cfg(LLVM_VERSION_11_OR_GREATER)]
+    LLVMBFloatTypeKind = 18,
+    #[cfg(LLVM_VERSION_12_OR_GREATER)]
+    LLVMX86_AMXTypeKind = 19,
 }
 
 #[repr(C)]
@@ -485,14 +561,25 @@ pub type LLVMAttributeIndex = ::libc::c_uint;
 pub type LLVMDiagnosticHandler =
     Option<extern "C" fn(arg1: LLVMDiagnosticInfoRef, arg2: *mut ::libc::c_void)>;
 pub type LLVMYieldCallback = Option<extern "C" fn(arg1: LLVMContextRef, arg2: *mut ::libc::c_void)>;
+}
 
-#[cfg(all(not(doc),LLVM_SYS_NOT_FOUND))]
-std::compile_error!(concat!(
-      "No suitable version of LLVM was found system-wide or pointed
-       to by LLVM_SYS_FEATURED_PREFIX.
-
-       Consider using `llvmenv` to compile an appropriate copy of LLVM, and
-       refer to the llvm-sys-featured documentation for more information.
+#[cfg(LLVM_SYS_RUNTIME_LOADING)]
+pub mod runtime;
+pub mod version;
+
+// With the abi-probe feature, the build script checks the layout of our
+// types against the LLVM headers, and these assertions fail if they differ.
+#[cfg(all(
+    feature = "abi-probe",
+    not(LLVM_SYS_NOT_FOUND),
+    not(LLVM_SYS_STUB),
+    not(LLVM_SYS_BINDGEN),
+    not(LLVM_SYS_RUNTIME_LOADING)
+))]
+mod abi_probe {
+    include!(concat!(env!("OUT_DIR"), "/abi_probe.rs"));
+}
 
-       llvm-sys-featured: https://crates.io/crates/llvm-sys-featured
-       llvmenv: https://crates.io/crates/llvmenv"));
+// The build script explains what it tried in this file.
+#[cfg(all(not(doc),LLVM_SYS_NOT_FOUND))]
+std::compile_error!(include_str!(concat!(env!("OUT_DIR"), "/llvm-discovery.txt")));
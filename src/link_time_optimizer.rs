@@ -19,7 +19,7 @@ pub enum llvm_lto_status_t {
     LLVM_LTO_NULL_OBJECT = 9,
 }
 
-extern "C" {
+extern_c! {
     pub fn llvm_create_optimizer() -> llvm_lto_t;
     pub fn llvm_destroy_optimizer(lto: llvm_lto_t);
     pub fn llvm_read_object_file(
@@ -10,7 +10,7 @@ pub enum LLVMLinkerMode {
     LLVMLinkerPreserveSource_Removed = 1,
 }
 
-extern "C" {
+extern_c! {
     /// Link the source module into the destination module.
     ///
     /// Destroys the source module, returns true on error. Use the diagnostic
@@ -92,7 +92,7 @@ pub type lto_diagnostic_handler_t = Option<
     ),
 >;
 
-extern "C" {
+extern_c! {
     pub fn lto_get_version() -> *const ::libc::c_char;
     pub fn lto_get_error_message() -> *const ::libc::c_char;
     pub fn lto_module_is_object_file(path: *const ::libc::c_char) -> lto_bool_t;
@@ -261,7 +261,7 @@ pub struct LTOObjectBuffer {
     Size: ::libc::size_t,
 }
 
-extern "C" {
+extern_c! {
     /// Instantiates a ThinLTO code generator.
     ///
     /// Returns null on error (check `lto_get_error_message` for details).
@@ -67,7 +67,7 @@ pub enum LLVMOpaqueObjectFile {}
 #[cfg_attr(LLVM_VERSION_9_OR_GREATER, deprecated(since = "LLVM 9.0"))]
 pub type LLVMObjectFileRef = *mut LLVMOpaqueObjectFile;
 
-extern "C" {
+extern_c! {
     /// Create a binary file from the given memory buffer.
     #[cfg(LLVM_VERSION_9_OR_GREATER)]
     pub fn LLVMCreateBinary(
@@ -93,23 +93,28 @@ extern "C" {
 
     #[cfg(LLVM_VERSION_9_OR_GREATER)]
     pub fn LLVMObjectFileCopySectionIterator(BR: LLVMBinaryRef) -> LLVMSectionIteratorRef;
+    #[cfg(LLVM_VERSION_8_OR_LOWER)]
     pub fn LLVMObjectFileIsSectionIteratorAtEnd(
-        #[cfg(LLVM_VERSION_8_OR_LOWER)]
         ObjectFile: LLVMObjectFileRef,
-        #[cfg(LLVM_VERSION_9_OR_GREATER)]
+        SI: LLVMSectionIteratorRef,
+    ) -> LLVMBool;
+    #[cfg(LLVM_VERSION_9_OR_GREATER)]
+    pub fn LLVMObjectFileIsSectionIteratorAtEnd(
         BR: LLVMBinaryRef,
         SI: LLVMSectionIteratorRef,
     ) -> LLVMBool;
     #[cfg(LLVM_VERSION_9_OR_GREATER)]
     pub fn LLVMObjectFileCopySymbolIterator(BR: LLVMBinaryRef) -> LLVMSymbolIteratorRef;
+    #[cfg(LLVM_VERSION_8_OR_LOWER)]
     pub fn LLVMObjectFileIsSymbolIteratorAtEnd(
-        #[cfg(LLVM_VERSION_8_OR_LOWER)]
         ObjectFile: LLVMObjectFileRef,
-        #[cfg(LLVM_VERSION_9_OR_GREATER)]
-        BR: LLVMBinaryRef,
         SI: LLVMSymbolIteratorRef,
     ) -> LLVMBool;
     #[cfg(LLVM_VERSION_9_OR_GREATER)]
+    pub fn LLVMObjectFileIsSymbolIteratorAtEnd(
+        BR: LLVMBinaryRef,
+        SI: LLVMSymbolIteratorRef,
+    ) -> LLVMBool;
     pub fn LLVMDisposeSectionIterator(SI: LLVMSectionIteratorRef);
 
     pub fn LLVMMoveToNextSection(SI: LLVMSectionIteratorRef);
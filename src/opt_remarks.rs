@@ -5,37 +5,37 @@ use super::prelude::*;
 #[repr(C)]
 #[derive(Debug)]
 pub struct LLVMOptRemarkStringRef {
-    Str: *const libc::c_char,
-    Len: u32,
+    pub Str: *const libc::c_char,
+    pub Len: u32,
 }
 
 #[repr(C)]
 #[derive(Debug)]
 pub struct LLVMOptRemarkDebugLoc {
-    SourceFile: LLVMOptRemarkStringRef,
-    SourceLineNumber: u32,
-    SourceColumnNumber: u32,
+    pub SourceFile: LLVMOptRemarkStringRef,
+    pub SourceLineNumber: u32,
+    pub SourceColumnNumber: u32,
 }
 
 #[repr(C)]
 #[derive(Debug)]
 pub struct LLVMOptRemarkArg {
-    Key: LLVMOptRemarkStringRef,
-    Value: LLVMOptRemarkStringRef,
-    DebugLoc: LLVMOptRemarkDebugLoc,
+    pub Key: LLVMOptRemarkStringRef,
+    pub Value: LLVMOptRemarkStringRef,
+    pub DebugLoc: LLVMOptRemarkDebugLoc,
 }
 
 #[repr(C)]
 #[derive(Debug)]
 pub struct LLVMOptRemarkEntry {
-    RemarkType: LLVMOptRemarkStringRef,
-    PassName: LLVMOptRemarkStringRef,
-    RemarkName: LLVMOptRemarkStringRef,
-    FunctionName: LLVMOptRemarkStringRef,
-    DebugLoc: LLVMOptRemarkDebugLoc,
-    Hotness: u32,
-    NumArgs: u32,
-    Args: *mut LLVMOptRemarkArg,
+    pub RemarkType: LLVMOptRemarkStringRef,
+    pub PassName: LLVMOptRemarkStringRef,
+    pub RemarkName: LLVMOptRemarkStringRef,
+    pub FunctionName: LLVMOptRemarkStringRef,
+    pub DebugLoc: LLVMOptRemarkDebugLoc,
+    pub Hotness: u32,
+    pub NumArgs: u32,
+    pub Args: *mut LLVMOptRemarkArg,
 }
 
 #[derive(Debug)]
@@ -43,7 +43,7 @@ pub enum LLVMOptRemarkOpaqueParser {}
 
 pub type LLVMOptRemarkParserRef = *mut LLVMOptRemarkOpaqueParser;
 
-extern "C" {
+extern_c! {
     pub fn LLVMOptRemarkParserCreate(Buf: *const libc::c_void, Size: u64)
         -> LLVMOptRemarkParserRef;
     pub fn LLVMOptRemarkParserGetNext(Parser: LLVMOptRemarkParserRef) -> *mut LLVMOptRemarkEntry;
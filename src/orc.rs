@@ -1,21 +1,30 @@
-//! The ORC JIT.
+//! The ORC JIT: the original API (ORCv1) up to LLVM 11, which LLVM 12
+//! removed, and the one that replaced it (ORCv2) from LLVM 11.
 
 use super::error::LLVMErrorRef;
 use super::prelude::*;
 use super::target_machine::LLVMTargetMachineRef;
 
+#[cfg(LLVM_VERSION_11_OR_LOWER)]
 #[derive(Debug)]
 pub enum LLVMOrcOpaqueJITStack {}
 
+#[cfg(LLVM_VERSION_11_OR_LOWER)]
 pub type LLVMOrcJITStackRef = *mut LLVMOrcOpaqueJITStack;
+#[cfg(LLVM_VERSION_11_OR_LOWER)]
 pub type LLVMOrcModuleHandle = u64;
+#[cfg(LLVM_VERSION_11_OR_LOWER)]
 pub type LLVMOrcTargetAddress = u64;
 
+#[cfg(LLVM_VERSION_11_OR_LOWER)]
 pub type LLVMOrcSymbolResolverFn =
     Option<extern "C" fn(*const ::libc::c_char, *mut ::libc::c_void) -> u64>;
+#[cfg(LLVM_VERSION_11_OR_LOWER)]
 pub type LLVMOrcLazyCompileCallbackFn =
     Option<extern "C" fn(LLVMOrcJITStackRef, *mut ::libc::c_void)>;
-extern "C" {
+
+#[cfg(LLVM_VERSION_11_OR_LOWER)]
+extern_c! {
     /// Create an ORC JIT stack.
     ///
     /// The client owns the returned stack and must call OrcDisposeInstance
@@ -130,3 +139,340 @@ extern "C" {
         L: LLVMJITEventListenerRef,
     );
 }
+
+// ORCv2, new in LLVM 11.
+
+/// Represents an address in the target process.
+#[cfg(LLVM_VERSION_11_OR_GREATER)]
+pub type LLVMOrcJITTargetAddress = u64;
+
+/// Represents an address in the executor process.
+#[cfg(LLVM_VERSION_13_OR_GREATER)]
+pub type LLVMOrcExecutorAddress = u64;
+
+#[cfg(LLVM_VERSION_11_OR_GREATER)]
+#[derive(Debug)]
+pub enum LLVMOrcOpaqueExecutionSession {}
+#[cfg(LLVM_VERSION_11_OR_GREATER)]
+pub type LLVMOrcExecutionSessionRef = *mut LLVMOrcOpaqueExecutionSession;
+
+#[cfg(LLVM_VERSION_11_OR_GREATER)]
+#[derive(Debug)]
+pub enum LLVMOrcQuaqueSymbolStringPoolEntryPtr {}
+#[cfg(LLVM_VERSION_11_OR_GREATER)]
+pub type LLVMOrcSymbolStringPoolEntryRef = *mut LLVMOrcQuaqueSymbolStringPoolEntryPtr;
+
+#[cfg(LLVM_VERSION_11_OR_GREATER)]
+#[derive(Debug)]
+pub enum LLVMOrcOpaqueJITDylib {}
+#[cfg(LLVM_VERSION_11_OR_GREATER)]
+pub type LLVMOrcJITDylibRef = *mut LLVMOrcOpaqueJITDylib;
+
+#[cfg(all(LLVM_VERSION_11_OR_GREATER, LLVM_VERSION_11_OR_LOWER))]
+#[derive(Debug)]
+pub enum LLVMOrcOpaqueJITDylibDefinitionGenerator {}
+#[cfg(all(LLVM_VERSION_11_OR_GREATER, LLVM_VERSION_11_OR_LOWER))]
+pub type LLVMOrcJITDylibDefinitionGeneratorRef = *mut LLVMOrcOpaqueJITDylibDefinitionGenerator;
+
+/// A reference to an orc::DefinitionGenerator (called
+/// `LLVMOrcJITDylibDefinitionGeneratorRef` in LLVM 11).
+#[cfg(LLVM_VERSION_12_OR_GREATER)]
+#[derive(Debug)]
+pub enum LLVMOrcOpaqueDefinitionGenerator {}
+#[cfg(LLVM_VERSION_12_OR_GREATER)]
+pub type LLVMOrcDefinitionGeneratorRef = *mut LLVMOrcOpaqueDefinitionGenerator;
+
+/// Predicate function for SymbolStringPoolEntries.
+#[cfg(all(LLVM_VERSION_11_OR_GREATER, LLVM_VERSION_11_OR_LOWER))]
+pub type LLVMOrcSymbolPredicate = Option<
+    extern "C" fn(Sym: LLVMOrcSymbolStringPoolEntryRef, Ctx: *mut ::libc::c_void) -> ::libc::c_int,
+>;
+/// Predicate function for SymbolStringPoolEntries.
+#[cfg(LLVM_VERSION_12_OR_GREATER)]
+pub type LLVMOrcSymbolPredicate = Option<
+    extern "C" fn(Ctx: *mut ::libc::c_void, Sym: LLVMOrcSymbolStringPoolEntryRef) -> ::libc::c_int,
+>;
+
+#[cfg(LLVM_VERSION_11_OR_GREATER)]
+#[derive(Debug)]
+pub enum LLVMOrcOpaqueThreadSafeContext {}
+#[cfg(LLVM_VERSION_11_OR_GREATER)]
+pub type LLVMOrcThreadSafeContextRef = *mut LLVMOrcOpaqueThreadSafeContext;
+
+#[cfg(LLVM_VERSION_11_OR_GREATER)]
+#[derive(Debug)]
+pub enum LLVMOrcOpaqueThreadSafeModule {}
+#[cfg(LLVM_VERSION_11_OR_GREATER)]
+pub type LLVMOrcThreadSafeModuleRef = *mut LLVMOrcOpaqueThreadSafeModule;
+
+#[cfg(LLVM_VERSION_11_OR_GREATER)]
+#[derive(Debug)]
+pub enum LLVMOrcOpaqueJITTargetMachineBuilder {}
+#[cfg(LLVM_VERSION_11_OR_GREATER)]
+pub type LLVMOrcJITTargetMachineBuilderRef = *mut LLVMOrcOpaqueJITTargetMachineBuilder;
+
+#[cfg(LLVM_VERSION_11_OR_GREATER)]
+#[derive(Debug)]
+pub enum LLVMOrcOpaqueLLJITBuilder {}
+#[cfg(LLVM_VERSION_11_OR_GREATER)]
+pub type LLVMOrcLLJITBuilderRef = *mut LLVMOrcOpaqueLLJITBuilder;
+
+#[cfg(LLVM_VERSION_11_OR_GREATER)]
+#[derive(Debug)]
+pub enum LLVMOrcOpaqueLLJIT {}
+#[cfg(LLVM_VERSION_11_OR_GREATER)]
+pub type LLVMOrcLLJITRef = *mut LLVMOrcOpaqueLLJIT;
+
+#[cfg(LLVM_VERSION_11_OR_GREATER)]
+extern_c! {
+    /// Intern a string in the ExecutionSession's SymbolStringPool and return a
+    /// reference to it.
+    ///
+    /// This increments the ref-count of the pool entry, and the returned value
+    /// should be released once the client is done with it by calling
+    /// LLVMOrcReleaseSymbolStringPoolEntry.
+    pub fn LLVMOrcExecutionSessionIntern(
+        ES: LLVMOrcExecutionSessionRef,
+        Name: *const ::libc::c_char,
+    ) -> LLVMOrcSymbolStringPoolEntryRef;
+
+    /// Reduces the ref-count of a SymbolStringPool entry.
+    pub fn LLVMOrcReleaseSymbolStringPoolEntry(S: LLVMOrcSymbolStringPoolEntryRef);
+
+    /// Dispose of a JITDylib::DefinitionGenerator.
+    ///
+    /// This should only be called if ownership has not been passed to a
+    /// JITDylib (e.g. because some error prevented the client from calling
+    /// LLVMOrcJITDylibAddGenerator).
+    #[cfg(LLVM_VERSION_11_OR_LOWER)]
+    pub fn LLVMOrcDisposeJITDylibDefinitionGenerator(DG: LLVMOrcJITDylibDefinitionGeneratorRef);
+    /// Dispose of a JITDylib::DefinitionGenerator.
+    ///
+    /// This should only be called if ownership has not been passed to a
+    /// JITDylib (e.g. because some error prevented the client from calling
+    /// LLVMOrcJITDylibAddGenerator).
+    #[cfg(LLVM_VERSION_12_OR_GREATER)]
+    pub fn LLVMOrcDisposeDefinitionGenerator(DG: LLVMOrcDefinitionGeneratorRef);
+
+    /// Add a JITDylib::DefinitionGenerator to the given JITDylib.
+    ///
+    /// The JITDylib will take ownership of the given generator: the client is
+    /// no longer responsible for managing its memory.
+    #[cfg(LLVM_VERSION_11_OR_LOWER)]
+    pub fn LLVMOrcJITDylibAddGenerator(
+        JD: LLVMOrcJITDylibRef,
+        DG: LLVMOrcJITDylibDefinitionGeneratorRef,
+    );
+    /// Add a DefinitionGenerator to the given JITDylib.
+    ///
+    /// The JITDylib will take ownership of the given generator: the client is
+    /// no longer responsible for managing its memory.
+    #[cfg(LLVM_VERSION_12_OR_GREATER)]
+    pub fn LLVMOrcJITDylibAddGenerator(JD: LLVMOrcJITDylibRef, DG: LLVMOrcDefinitionGeneratorRef);
+
+    /// Get a DynamicLibrarySearchGenerator that will reflect process symbols
+    /// into the JITDylib.
+    ///
+    /// On success the resulting generator is owned by the client and must be
+    /// passed to LLVMOrcJITDylibAddGenerator or disposed of with
+    /// LLVMOrcDisposeJITDylibDefinitionGenerator (LLVMOrcDisposeDefinitionGenerator
+    /// from LLVM 12).
+    ///
+    /// The GlobalPrefix argument specifies the character that appears on the
+    /// front of linker-mangled symbols for the target platform (e.g. '_' on
+    /// MachO). If non-null, the Filter argument specifies a predicate that
+    /// will be used to filter the exported symbols.
+    #[cfg(LLVM_VERSION_11_OR_LOWER)]
+    pub fn LLVMOrcCreateDynamicLibrarySearchGeneratorForProcess(
+        Result: *mut LLVMOrcJITDylibDefinitionGeneratorRef,
+        GlobalPrefix: ::libc::c_char,
+        Filter: LLVMOrcSymbolPredicate,
+        FilterCtx: *mut ::libc::c_void,
+    ) -> LLVMErrorRef;
+    #[cfg(LLVM_VERSION_12_OR_GREATER)]
+    pub fn LLVMOrcCreateDynamicLibrarySearchGeneratorForProcess(
+        Result: *mut LLVMOrcDefinitionGeneratorRef,
+        GlobalPrefix: ::libc::c_char,
+        Filter: LLVMOrcSymbolPredicate,
+        FilterCtx: *mut ::libc::c_void,
+    ) -> LLVMErrorRef;
+
+    /// Create a ThreadSafeContext containing a new LLVMContext.
+    ///
+    /// Ownership of the underlying ThreadSafeContext data is shared: clients
+    /// can and should dispose of their ThreadSafeContext as soon as they no
+    /// longer need to refer to it directly. Other references (e.g. from
+    /// ThreadSafeModules) will keep the data alive as long as it is needed.
+    pub fn LLVMOrcCreateNewThreadSafeContext() -> LLVMOrcThreadSafeContextRef;
+
+    /// Get a reference to the wrapped LLVMContext.
+    pub fn LLVMOrcThreadSafeContextGetContext(TSCtx: LLVMOrcThreadSafeContextRef)
+        -> LLVMContextRef;
+
+    /// Dispose of a ThreadSafeContext.
+    pub fn LLVMOrcDisposeThreadSafeContext(TSCtx: LLVMOrcThreadSafeContextRef);
+
+    /// Create a ThreadSafeModule wrapper around the given LLVM module.
+    ///
+    /// This takes ownership of the M argument which should not be disposed of
+    /// or referenced after this function returns.
+    ///
+    /// Ownership of the ThreadSafeModule is unique: if it is transferred to
+    /// the JIT (e.g. by LLVMOrcLLJITAddLLVMIRModule) then the client is no
+    /// longer responsible for it. If it is not transferred to the JIT then
+    /// the client should call LLVMOrcDisposeThreadSafeModule to dispose of it.
+    pub fn LLVMOrcCreateNewThreadSafeModule(
+        M: LLVMModuleRef,
+        TSCtx: LLVMOrcThreadSafeContextRef,
+    ) -> LLVMOrcThreadSafeModuleRef;
+
+    /// Dispose of a ThreadSafeModule.
+    ///
+    /// This should only be called if ownership has not been passed to LLJIT
+    /// (e.g. because some error prevented the client from adding this
+    /// buffer).
+    pub fn LLVMOrcDisposeThreadSafeModule(TSM: LLVMOrcThreadSafeModuleRef);
+
+    /// Create a JITTargetMachineBuilder by detecting the host.
+    ///
+    /// On success the client owns the resulting JITTargetMachineBuilder. It
+    /// must be passed to a consuming operation (e.g.
+    /// LLVMOrcLLJITBuilderSetJITTargetMachineBuilder) or disposed of by
+    /// calling LLVMOrcDisposeJITTargetMachineBuilder.
+    pub fn LLVMOrcJITTargetMachineBuilderDetectHost(
+        Result: *mut LLVMOrcJITTargetMachineBuilderRef,
+    ) -> LLVMErrorRef;
+
+    /// Create a JITTargetMachineBuilder from the given TargetMachine template.
+    ///
+    /// This operation takes ownership of the given TargetMachine and destroys
+    /// it before returning. The resulting JITTargetMachineBuilder is owned by
+    /// the client and must be passed to a consuming operation or disposed of
+    /// by calling LLVMOrcDisposeJITTargetMachineBuilder.
+    pub fn LLVMOrcJITTargetMachineBuilderCreateFromTargetMachine(
+        TM: LLVMTargetMachineRef,
+    ) -> LLVMOrcJITTargetMachineBuilderRef;
+
+    /// Dispose of a JITTargetMachineBuilder.
+    pub fn LLVMOrcDisposeJITTargetMachineBuilder(JTMB: LLVMOrcJITTargetMachineBuilderRef);
+
+    /// Create an LLJITBuilder.
+    ///
+    /// The client owns the resulting LLJITBuilder and should dispose of it
+    /// using LLVMOrcDisposeLLJITBuilder once they are done with it.
+    pub fn LLVMOrcCreateLLJITBuilder() -> LLVMOrcLLJITBuilderRef;
+
+    /// Dispose of an LLVMOrcLLJITBuilderRef.
+    ///
+    /// This should only be called if ownership has not been passed to
+    /// LLVMOrcCreateLLJIT (e.g. because some error prevented that function
+    /// from being called).
+    pub fn LLVMOrcDisposeLLJITBuilder(Builder: LLVMOrcLLJITBuilderRef);
+
+    /// Set the JITTargetMachineBuilder to be used when constructing the LLJIT
+    /// instance.
+    ///
+    /// Calling this function is optional: if it is not called then the
+    /// LLJITBuilder will use JITTargeTMachineBuilder::detectHost to construct
+    /// a JITTargetMachineBuilder. This function takes ownership of the JTMB
+    /// argument.
+    pub fn LLVMOrcLLJITBuilderSetJITTargetMachineBuilder(
+        Builder: LLVMOrcLLJITBuilderRef,
+        JTMB: LLVMOrcJITTargetMachineBuilderRef,
+    );
+
+    /// Create an LLJIT instance from an LLJITBuilder.
+    ///
+    /// This operation takes ownership of the Builder argument: clients should
+    /// not dispose of the builder after calling this function (even if the
+    /// function returns an error). If a null Builder argument is provided
+    /// then a default-constructed LLJITBuilder will be used.
+    ///
+    /// On success the resulting LLJIT instance is uniquely owned by the
+    /// client and automatically manages the memory of all JIT'd code and all
+    /// modules that are transferred to it (e.g. via
+    /// LLVMOrcLLJITAddLLVMIRModule). Disposing of the LLJIT instance will
+    /// free all memory managed by the JIT, including JIT'd code and
+    /// not-yet-compiled modules.
+    pub fn LLVMOrcCreateLLJIT(
+        Result: *mut LLVMOrcLLJITRef,
+        Builder: LLVMOrcLLJITBuilderRef,
+    ) -> LLVMErrorRef;
+
+    /// Dispose of an LLJIT instance.
+    pub fn LLVMOrcDisposeLLJIT(J: LLVMOrcLLJITRef) -> LLVMErrorRef;
+
+    /// Get a reference to the ExecutionSession for this LLJIT instance.
+    ///
+    /// The ExecutionSession is owned by the LLJIT instance. The client is not
+    /// responsible for managing its memory.
+    pub fn LLVMOrcLLJITGetExecutionSession(J: LLVMOrcLLJITRef) -> LLVMOrcExecutionSessionRef;
+
+    /// Return a reference to the Main JITDylib.
+    ///
+    /// The JITDylib is owned by the LLJIT instance. The client is not
+    /// responsible for managing its memory.
+    pub fn LLVMOrcLLJITGetMainJITDylib(J: LLVMOrcLLJITRef) -> LLVMOrcJITDylibRef;
+
+    /// Return the target triple for this LLJIT instance.
+    ///
+    /// This string is owned by the LLJIT instance and should not be freed by
+    /// the client.
+    pub fn LLVMOrcLLJITGetTripleString(J: LLVMOrcLLJITRef) -> *const ::libc::c_char;
+
+    /// Returns the global prefix character according to the LLJIT's
+    /// DataLayout.
+    pub fn LLVMOrcLLJITGetGlobalPrefix(J: LLVMOrcLLJITRef) -> ::libc::c_char;
+
+    /// Mangles the given string according to the LLJIT instance's DataLayout,
+    /// then interns the result in the SymbolStringPool and returns a
+    /// reference to the pool entry.
+    ///
+    /// Clients should call LLVMOrcReleaseSymbolStringPoolEntry to decrement
+    /// the ref-count on the pool entry once they are finished with this
+    /// value.
+    pub fn LLVMOrcLLJITMangleAndIntern(
+        J: LLVMOrcLLJITRef,
+        UnmangledName: *const ::libc::c_char,
+    ) -> LLVMOrcSymbolStringPoolEntryRef;
+
+    /// Add a buffer representing an object file to the given JITDylib in the
+    /// given LLJIT instance.
+    ///
+    /// This operation transfers ownership of the buffer to the LLJIT
+    /// instance. The buffer should not be disposed of or referenced once this
+    /// function returns.
+    pub fn LLVMOrcLLJITAddObjectFile(
+        J: LLVMOrcLLJITRef,
+        JD: LLVMOrcJITDylibRef,
+        ObjBuffer: LLVMMemoryBufferRef,
+    ) -> LLVMErrorRef;
+
+    /// Add an IR module to the given JITDylib of the given LLJIT instance.
+    ///
+    /// This operation transfers ownership of the TSM argument to the LLJIT
+    /// instance. The TSM argument should not be disposed of or referenced
+    /// once this function returns.
+    pub fn LLVMOrcLLJITAddLLVMIRModule(
+        J: LLVMOrcLLJITRef,
+        JD: LLVMOrcJITDylibRef,
+        TSM: LLVMOrcThreadSafeModuleRef,
+    ) -> LLVMErrorRef;
+
+    /// Look up the given symbol in the main JITDylib of the given LLJIT
+    /// instance.
+    ///
+    /// This operation does not take ownership of the Name argument.
+    #[cfg(LLVM_VERSION_12_OR_LOWER)]
+    pub fn LLVMOrcLLJITLookup(
+        J: LLVMOrcLLJITRef,
+        Result: *mut LLVMOrcJITTargetAddress,
+        Name: *const ::libc::c_char,
+    ) -> LLVMErrorRef;
+    #[cfg(LLVM_VERSION_13_OR_GREATER)]
+    pub fn LLVMOrcLLJITLookup(
+        J: LLVMOrcLLJITRef,
+        Result: *mut LLVMOrcExecutorAddress,
+        Name: *const ::libc::c_char,
+    ) -> LLVMErrorRef;
+}
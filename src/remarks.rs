@@ -17,7 +17,7 @@ pub enum LLVMRemarkOpaqueString {}
 /// String containing a buffer and a length. The buffer is not guaranteed to be zero-terminated.
 pub type LLVMRemarkStringRef = *mut LLVMRemarkOpaqueString;
 
-extern "C" {
+extern_c! {
     /// Returns the buffer holding the string.
     pub fn LLVMRemarkStringGetData(String: LLVMRemarkStringRef) -> *const ::libc::c_char;
 
@@ -30,7 +30,7 @@ pub enum LLVMRemarkOpaqueDebugLoc {}
 /// DebugLoc containing File, Line and Column.
 pub type LLVMRemarkDebugLocRef = *mut LLVMRemarkOpaqueDebugLoc;
 
-extern "C" {
+extern_c! {
     /// Return the path to the source file for a debug location.
     pub fn LLVMRemarkDebugLocGetSourceFilePath(DL: LLVMRemarkDebugLocRef) -> LLVMRemarkStringRef;
 
@@ -48,7 +48,7 @@ pub enum LLVMRemarkOpaqueArg {}
 /// is a symbol that names a function.
 pub type LLVMRemarkArgRef = *mut LLVMRemarkOpaqueArg;
 
-extern "C" {
+extern_c! {
     /// Returns the key of an argument. The key defines what the value is, and the
     /// same key can appear multiple times in the list of arguments.
     pub fn LLVMRemarkArgGetKey(Arg: LLVMRemarkArgRef) -> LLVMRemarkStringRef;
@@ -64,7 +64,7 @@ pub enum LLVMRemarkOpaqueEntry {}
 /// A remark emitted by the compiler.
 pub type LLVMRemarkEntryRef = *mut LLVMRemarkOpaqueEntry;
 
-extern "C" {
+extern_c! {
     /// Free the resources used by the remark entry.
     pub fn LLVMRemarkEntryDispose(Remark: LLVMRemarkEntryRef);
 
@@ -103,7 +103,7 @@ extern "C" {
 pub enum LLVMRemarkOpaqueParser {}
 pub type LLVMRemarkParserRef = *mut LLVMRemarkOpaqueParser;
 
-extern "C" {
+extern_c! {
     /// Creates a remark parser that can be used to parse the buffer located in
     /// Buf of size Size bytes.
     pub fn LLVMRemarkParserCreateYAML(Buf: *const ::libc::c_void, Size: u64)
@@ -132,7 +132,7 @@ pub const REMARKS_API_VERSION: u32 = 0;
 #[cfg(LLVM_VERSION_10_OR_GREATER)]
 pub const REMARKS_API_VERSION: u32 = 1;
 
-extern "C" {
+extern_c! {
     /// Returns the version of the remarks library.
     pub fn LLVMRemarkVersion() -> u32;
 }
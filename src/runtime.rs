@@ -0,0 +1,355 @@
+//! Loading LLVM at run time, with the runtime-loading feature.
+//!
+//! Nothing is linked against LLVM in this mode. Instead, every function in the
+//! bindings is a shim that calls through a table of function pointers, which
+//! [`load`] fills in from a shared library (`libLLVM-N.so` or equivalent)
+//! chosen at run time. `load` must succeed before any other function in this
+//! crate is called; calling one earlier panics. So does calling a function
+//! that the loaded library doesn't have: see [`load`] for which ones it may
+//! lack.
+//!
+//! The shims are plain Rust functions rather than `extern "C"` ones (so that
+//! these panics unwind instead of aborting), so unlike with a linked LLVM they
+//! can't be passed where a C function pointer is expected.
+//!
+//! ```no_run
+//! llvm_sys_featured::runtime::load("/usr/lib/llvm-10/lib/libLLVM-10.so")
+//!     .expect("Failed to load LLVM");
+//! ```
+
+use std::error::Error;
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::OnceLock;
+
+use super::prelude::LLVMBool;
+use super::version::VersionMismatch;
+
+// The functions in the bindings, as `(name, required)` sorted by name, with a
+// nul at the end of each name. This is `SYMBOLS`, written by the build script.
+include!(concat!(env!("OUT_DIR"), "/runtime_symbols.rs"));
+
+/// The target backends LLVM can be built with, as they appear in the names of
+/// their initialization functions.
+static TARGETS: &[&str] = &[
+    "AArch64",
+    "AMDGPU",
+    "ARC",
+    "ARM",
+    "AVR",
+    "BPF",
+    "CSKY",
+    "Hexagon",
+    "Lanai",
+    "LoongArch",
+    "M68k",
+    "MSP430",
+    "Mips",
+    "NVPTX",
+    "PowerPC",
+    "RISCV",
+    "Sparc",
+    "SystemZ",
+    "VE",
+    "WebAssembly",
+    "X86",
+    "XCore",
+];
+
+/// The loaded library, and the address of each function in `SYMBOLS` (or 0
+/// for those it doesn't have).
+struct Table {
+    library: usize,
+    addresses: Vec<usize>,
+}
+
+static TABLE: OnceLock<Table> = OnceLock::new();
+
+/// An error from [`load`].
+#[derive(Debug)]
+pub enum LoadError {
+    /// LLVM is already loaded.
+    AlreadyLoaded,
+    /// The library couldn't be opened.
+    Open { path: PathBuf, message: String },
+    /// The library doesn't have these functions, which the bindings for the
+    /// selected LLVM version declare. This usually means it is a different
+    /// version of LLVM.
+    MissingSymbols {
+        path: PathBuf,
+        symbols: Vec<&'static str>,
+    },
+    /// The library is a different major version of LLVM than the selected
+    /// one. This is only checked with the `check-version` feature.
+    Version(VersionMismatch),
+}
+
+impl fmt::Display for LoadError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            LoadError::AlreadyLoaded => write!(f, "LLVM is already loaded"),
+            LoadError::Open {
+                ref path,
+                ref message,
+            } => write!(f, "failed to load {}: {}", path.display(), message),
+            LoadError::MissingSymbols {
+                ref path,
+                ref symbols,
+            } => write!(
+                f,
+                "{} is missing {} functions of the LLVM C API: {}",
+                path.display(),
+                symbols.len(),
+                symbols.join(", ")
+            ),
+            LoadError::Version(ref mismatch) => write!(f, "{}", mismatch),
+        }
+    }
+}
+
+impl Error for LoadError {}
+
+/// Load LLVM from the shared library at `path`, and look up all of the
+/// functions in the bindings. This fails, naming them, if any of the functions
+/// are missing, except for these, which are looked up but may be missing:
+///
+/// - the initialization functions of target backends that the library wasn't
+///   built with;
+/// - the functions of the `lto` and `link_time_optimizer` modules, and
+///   `LLVMRemarkVersion`, which are in `libLTO` and `libRemarks` and may not be
+///   exported by the main library;
+/// - functions that later versions of LLVM removed, so that a newer LLVM than
+///   the selected version can be loaded.
+///
+/// With the `check-version` feature, this also fails if the library is a
+/// different major version of LLVM than the selected one (see
+/// [`version::check`](super::version::check)).
+pub fn load<P: AsRef<Path>>(path: P) -> Result<(), LoadError> {
+    let path = path.as_ref();
+    if TABLE.get().is_some() {
+        return Err(LoadError::AlreadyLoaded);
+    }
+    let library = unsafe { sys::open(path) }.map_err(|message| LoadError::Open {
+        path: path.to_owned(),
+        message,
+    })?;
+    let addresses: Vec<usize> = SYMBOLS
+        .iter()
+        .map(|&(name, _)| unsafe { sys::symbol(library, name) })
+        .collect();
+    let missing: Vec<&'static str> = SYMBOLS
+        .iter()
+        .zip(&addresses)
+        .filter(|&(&(_, required), &address)| required && address == 0)
+        .map(|(&(name, _), _)| name.trim_end_matches('\0'))
+        .collect();
+    if !missing.is_empty() {
+        unsafe { sys::close(library) };
+        return Err(LoadError::MissingSymbols {
+            path: path.to_owned(),
+            symbols: missing,
+        });
+    }
+    #[cfg(feature = "check-version")]
+    {
+        let symbol = |name: &str| match unsafe { sys::symbol(library, name) } {
+            0 => None,
+            address => Some(address),
+        };
+        if let Err(mismatch) = super::version::check_library(&symbol) {
+            unsafe { sys::close(library) };
+            return Err(LoadError::Version(mismatch));
+        }
+    }
+    // Another thread may have loaded LLVM in the meantime.
+    TABLE.set(Table { library, addresses }).map_err(|table| {
+        unsafe { sys::close(table.library) };
+        LoadError::AlreadyLoaded
+    })
+}
+
+/// Whether LLVM has been loaded with [`load`].
+pub fn is_loaded() -> bool {
+    TABLE.get().is_some()
+}
+
+/// The address of `name`, which ends in a nul, in the loaded library, if
+/// LLVM is loaded and has it.
+pub(crate) fn symbol(name: &str) -> Option<usize> {
+    let table = TABLE.get()?;
+    match unsafe { sys::symbol(table.library, name) } {
+        0 => None,
+        address => Some(address),
+    }
+}
+
+fn table() -> &'static Table {
+    TABLE
+        .get()
+        .expect("llvm-sys-featured: LLVM was used before being loaded with runtime::load")
+}
+
+/// A function in the table, as used by each shim, which caches its address.
+#[doc(hidden)]
+pub struct Function {
+    name: &'static str,
+    address: AtomicUsize,
+}
+
+impl Function {
+    pub const fn new(name: &'static str) -> Self {
+        Function {
+            name,
+            address: AtomicUsize::new(0),
+        }
+    }
+
+    /// The address of the function in the loaded library.
+    pub fn address(&self) -> *const libc::c_void {
+        let mut address = self.address.load(Ordering::Relaxed);
+        if address == 0 {
+            let name = self.name.trim_end_matches('\0');
+            address = SYMBOLS
+                .binary_search_by_key(&self.name, |&(name, _)| name)
+                .map(|index| table().addresses[index])
+                .unwrap_or(0);
+            if address == 0 {
+                panic!("llvm-sys-featured: The loaded LLVM doesn't have {}", name);
+            }
+            self.address.store(address, Ordering::Relaxed);
+        }
+        address as *const libc::c_void
+    }
+}
+
+/// Call `LLVMInitialize<Target><kind>` for each target in the loaded library,
+/// as `LLVMInitializeAll<kind>s` does.
+pub(crate) fn initialize_all(kind: &str) {
+    for target in TARGETS {
+        unsafe { initialize(target, kind) };
+    }
+}
+
+/// Call `LLVMInitialize<Target><kind>` for the host's target and each of
+/// `kinds`, as `LLVMInitializeNative<kind>` does, returning 1 if the library
+/// doesn't have them.
+pub(crate) fn initialize_native(kinds: &[&str]) -> LLVMBool {
+    let target = match native_target() {
+        Some(target) => target,
+        None => return 1,
+    };
+    for kind in kinds {
+        if !unsafe { initialize(target, kind) } {
+            return 1;
+        }
+    }
+    0
+}
+
+/// Call `LLVMInitialize<target><kind>` if the loaded library has it.
+unsafe fn initialize(target: &str, kind: &str) -> bool {
+    let name = format!("LLVMInitialize{}{}\0", target, kind);
+    let address = sys::symbol(table().library, &name);
+    if address == 0 {
+        return false;
+    }
+    let function = std::mem::transmute::<usize, unsafe extern "C" fn()>(address);
+    function();
+    true
+}
+
+/// The LLVM target for the architecture this crate was compiled for.
+fn native_target() -> Option<&'static str> {
+    let arch = std::env::consts::ARCH;
+    Some(match arch {
+        "x86" | "x86_64" => "X86",
+        "aarch64" => "AArch64",
+        "arm" => "ARM",
+        "avr" => "AVR",
+        "bpf" => "BPF",
+        "csky" => "CSKY",
+        "hexagon" => "Hexagon",
+        "loongarch64" => "LoongArch",
+        "m68k" => "M68k",
+        "mips" | "mips64" => "Mips",
+        "msp430" => "MSP430",
+        "nvptx64" => "NVPTX",
+        "powerpc" | "powerpc64" => "PowerPC",
+        "riscv32" | "riscv64" => "RISCV",
+        "s390x" => "SystemZ",
+        "sparc" | "sparc64" => "Sparc",
+        "wasm32" | "wasm64" => "WebAssembly",
+        _ => return None,
+    })
+}
+
+#[cfg(unix)]
+mod sys {
+    use std::ffi::{CStr, CString};
+    use std::os::unix::ffi::OsStrExt;
+    use std::path::Path;
+
+    pub unsafe fn open(path: &Path) -> Result<usize, String> {
+        let path = CString::new(path.as_os_str().as_bytes()).map_err(|e| e.to_string())?;
+        let library = libc::dlopen(path.as_ptr(), libc::RTLD_NOW | libc::RTLD_LOCAL);
+        if library.is_null() {
+            Err(CStr::from_ptr(libc::dlerror())
+                .to_string_lossy()
+                .into_owned())
+        } else {
+            Ok(library as usize)
+        }
+    }
+
+    /// The address of `name`, which ends in a nul, or 0.
+    pub unsafe fn symbol(library: usize, name: &str) -> usize {
+        libc::dlsym(
+            library as *mut libc::c_void,
+            name.as_ptr() as *const libc::c_char,
+        ) as usize
+    }
+
+    pub unsafe fn close(library: usize) {
+        libc::dlclose(library as *mut libc::c_void);
+    }
+}
+
+#[cfg(windows)]
+mod sys {
+    use std::io;
+    use std::os::windows::ffi::OsStrExt;
+    use std::path::Path;
+
+    #[link(name = "kernel32")]
+    extern "system" {
+        fn LoadLibraryW(name: *const u16) -> *mut libc::c_void;
+        fn GetProcAddress(
+            module: *mut libc::c_void,
+            name: *const libc::c_char,
+        ) -> *mut libc::c_void;
+        fn FreeLibrary(module: *mut libc::c_void) -> i32;
+    }
+
+    pub unsafe fn open(path: &Path) -> Result<usize, String> {
+        let path: Vec<u16> = path.as_os_str().encode_wide().chain(Some(0)).collect();
+        let library = LoadLibraryW(path.as_ptr());
+        if library.is_null() {
+            Err(io::Error::last_os_error().to_string())
+        } else {
+            Ok(library as usize)
+        }
+    }
+
+    /// The address of `name`, which ends in a nul, or 0.
+    pub unsafe fn symbol(library: usize, name: &str) -> usize {
+        GetProcAddress(
+            library as *mut libc::c_void,
+            name.as_ptr() as *const libc::c_char,
+        ) as usize
+    }
+
+    pub unsafe fn close(library: usize) {
+        FreeLibrary(library as *mut libc::c_void);
+    }
+}
@@ -0,0 +1,55 @@
+use std::marker::PhantomData;
+use std::mem;
+
+use super::{Context, Error, MemoryBuffer};
+use crate::object::{
+    LLVMBinaryGetType, LLVMBinaryRef, LLVMBinaryType, LLVMCreateBinary, LLVMDisposeBinary,
+};
+
+/// An owned `LLVMBinaryRef` reading a buffer that it borrows for `'a`,
+/// disposed of when dropped.
+///
+/// The `LLVMObjectFileCopy*Iterator` functions take
+/// [`as_raw`](Binary::as_raw).
+#[derive(Debug)]
+pub struct Binary<'a> {
+    raw: LLVMBinaryRef,
+    buffer: PhantomData<&'a MemoryBuffer>,
+}
+
+impl<'a> Binary<'a> {
+    /// Read the object file, archive or other binary in `buffer`. Bitcode is
+    /// read into `context`.
+    pub fn new(buffer: &'a MemoryBuffer, context: &'a Context) -> Result<Binary<'a>, Error> {
+        let mut message = std::ptr::null_mut();
+        let raw = unsafe { LLVMCreateBinary(buffer.as_raw(), context.as_raw(), &mut message) };
+        if raw.is_null() {
+            return Err(unsafe { Error::from_message(message) });
+        }
+        Ok(Binary {
+            raw,
+            buffer: PhantomData,
+        })
+    }
+
+    pub fn as_raw(&self) -> LLVMBinaryRef {
+        self.raw
+    }
+
+    /// Give up ownership of the binary, without disposing of it.
+    pub fn into_raw(self) -> LLVMBinaryRef {
+        let raw = self.raw;
+        mem::forget(self);
+        raw
+    }
+
+    pub fn binary_type(&self) -> LLVMBinaryType {
+        unsafe { LLVMBinaryGetType(self.raw) }
+    }
+}
+
+impl<'a> Drop for Binary<'a> {
+    fn drop(&mut self) {
+        unsafe { LLVMDisposeBinary(self.raw) }
+    }
+}
@@ -0,0 +1,61 @@
+use std::marker::PhantomData;
+use std::mem;
+
+use super::Context;
+use crate::core::{LLVMCreateBuilderInContext, LLVMDisposeBuilder, LLVMPositionBuilderAtEnd};
+use crate::prelude::*;
+
+/// An owned `LLVMBuilderRef` in the context `'ctx`, disposed of when dropped.
+///
+/// The `LLVMBuild*` functions take [`as_raw`](Builder::as_raw).
+#[derive(Debug)]
+pub struct Builder<'ctx> {
+    raw: LLVMBuilderRef,
+    context: PhantomData<&'ctx Context>,
+}
+
+impl<'ctx> Builder<'ctx> {
+    /// Create a builder in `context`.
+    pub fn new(context: &'ctx Context) -> Builder<'ctx> {
+        unsafe { Builder::from_raw(LLVMCreateBuilderInContext(context.as_raw())) }
+    }
+
+    /// Take ownership of a builder.
+    ///
+    /// # Safety
+    ///
+    /// `raw` must be a valid builder in a context that outlives `'ctx`, which
+    /// nothing else disposes of.
+    pub unsafe fn from_raw(raw: LLVMBuilderRef) -> Builder<'ctx> {
+        Builder {
+            raw,
+            context: PhantomData,
+        }
+    }
+
+    pub fn as_raw(&self) -> LLVMBuilderRef {
+        self.raw
+    }
+
+    /// Give up ownership of the builder, without disposing of it.
+    pub fn into_raw(self) -> LLVMBuilderRef {
+        let raw = self.raw;
+        mem::forget(self);
+        raw
+    }
+
+    /// Insert the instructions built next at the end of `block`.
+    ///
+    /// # Safety
+    ///
+    /// `block` must be a valid basic block in the builder's context.
+    pub unsafe fn position_at_end(&mut self, block: LLVMBasicBlockRef) {
+        LLVMPositionBuilderAtEnd(self.raw, block)
+    }
+}
+
+impl<'ctx> Drop for Builder<'ctx> {
+    fn drop(&mut self) {
+        unsafe { LLVMDisposeBuilder(self.raw) }
+    }
+}
@@ -0,0 +1,126 @@
+use std::mem::{ManuallyDrop, MaybeUninit};
+use std::ptr;
+
+use super::diagnostics::Handlers;
+use super::{
+    Builder, Diagnostic, DiagnosticCollector, DiagnosticHandlerGuard, Error, MemoryBuffer, Module,
+};
+use crate::core::{LLVMContextCreate, LLVMContextDispose};
+use crate::ir_reader::LLVMParseIRInContext;
+use crate::prelude::*;
+
+/// An owned `LLVMContextRef`, disposed of when dropped.
+#[derive(Debug)]
+pub struct Context {
+    raw: LLVMContextRef,
+    diagnostic_handlers: Box<Handlers>,
+}
+
+impl Context {
+    /// Create a new context.
+    ///
+    /// With the `check-version` feature, the first call panics if the LLVM in
+    /// use is a different major version than the selected one (see
+    /// [`version::check`](crate::version::check)).
+    pub fn new() -> Context {
+        #[cfg(feature = "check-version")]
+        crate::version::check_once();
+        unsafe { Context::from_raw(LLVMContextCreate()) }
+    }
+
+    /// Take ownership of a context.
+    ///
+    /// # Safety
+    ///
+    /// `raw` must be a valid context that nothing else disposes of.
+    pub unsafe fn from_raw(raw: LLVMContextRef) -> Context {
+        Context {
+            raw,
+            diagnostic_handlers: Box::new(Handlers::new()),
+        }
+    }
+
+    pub fn as_raw(&self) -> LLVMContextRef {
+        self.raw
+    }
+
+    /// Give up ownership of the context, without disposing of it. Any
+    /// diagnostic handlers installed with [`set_diagnostic_handler`] are
+    /// removed.
+    ///
+    /// [`set_diagnostic_handler`]: Context::set_diagnostic_handler
+    pub fn into_raw(self) -> LLVMContextRef {
+        let mut context = ManuallyDrop::new(self);
+        context.diagnostic_handlers.clear(context.raw);
+        unsafe { ptr::drop_in_place(&mut context.diagnostic_handlers) };
+        context.raw
+    }
+
+    /// Create an empty module in this context.
+    pub fn create_module(&self, name: &str) -> Module<'_> {
+        Module::new(name, self)
+    }
+
+    /// Create an instruction builder in this context.
+    pub fn create_builder(&self) -> Builder<'_> {
+        Builder::new(self)
+    }
+
+    /// Send the diagnostics that LLVM reports in this context to `handler`
+    /// until the returned guard is dropped, which restores the previous
+    /// handler. If several are installed, the latest one gets the
+    /// diagnostics.
+    ///
+    /// With a handler, LLVM doesn't print errors and exit the process, as
+    /// it does by default, so functions such as `LLVMLinkModules2` and
+    /// `LLVMParseBitcodeInContext2` return failure instead. Warnings and
+    /// remarks go to the handler as well.
+    ///
+    /// The handler is kept by the context, and must be `'static` in case the
+    /// guard is leaked. If it panics, the process is aborted, as the panic
+    /// can't unwind through LLVM.
+    pub fn set_diagnostic_handler<F>(&self, handler: F) -> DiagnosticHandlerGuard<'_>
+    where
+        F: FnMut(&Diagnostic) + 'static,
+    {
+        DiagnosticHandlerGuard::new(self, handler)
+    }
+
+    /// Collect the diagnostics that LLVM reports in this context, such as
+    /// why linking failed, until the returned collector is dropped. See
+    /// [`set_diagnostic_handler`](Context::set_diagnostic_handler).
+    pub fn collect_diagnostics(&self) -> DiagnosticCollector<'_> {
+        DiagnosticCollector::new(self)
+    }
+
+    pub(super) fn diagnostic_handlers(&self) -> &Handlers {
+        &self.diagnostic_handlers
+    }
+
+    /// Parse a module from LLVM IR, in text or bitcode. The buffer is
+    /// consumed, even if parsing fails.
+    pub fn parse_ir(&self, buffer: MemoryBuffer) -> Result<Module<'_>, Error> {
+        let mut module = MaybeUninit::uninit();
+        let mut message = ptr::null_mut();
+        unsafe {
+            if LLVMParseIRInContext(self.raw, buffer.into_raw(), module.as_mut_ptr(), &mut message)
+                != 0
+            {
+                return Err(Error::from_message(message));
+            }
+            Ok(Module::from_raw(module.assume_init()))
+        }
+    }
+}
+
+impl Default for Context {
+    fn default() -> Context {
+        Context::new()
+    }
+}
+
+impl Drop for Context {
+    fn drop(&mut self) {
+        unsafe { LLVMContextDispose(self.raw) }
+    }
+}
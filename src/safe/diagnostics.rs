@@ -0,0 +1,269 @@
+use std::cell::{Cell, RefCell};
+use std::fmt;
+use std::panic::{self, AssertUnwindSafe};
+use std::process;
+use std::rc::Rc;
+
+use super::{Context, LLVMString};
+use crate::core::{
+    LLVMContextGetDiagnosticContext, LLVMContextGetDiagnosticHandler,
+    LLVMContextSetDiagnosticHandler, LLVMGetDiagInfoDescription, LLVMGetDiagInfoSeverity,
+};
+use crate::prelude::*;
+use crate::{LLVMDiagnosticHandler, LLVMDiagnosticSeverity};
+
+/// A diagnostic reported by LLVM.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Diagnostic {
+    pub severity: LLVMDiagnosticSeverity,
+    pub message: String,
+}
+
+impl Diagnostic {
+    /// Copy the severity and description of a diagnostic.
+    ///
+    /// # Safety
+    ///
+    /// `info` must be a valid diagnostic.
+    pub unsafe fn from_raw(info: LLVMDiagnosticInfoRef) -> Diagnostic {
+        Diagnostic {
+            severity: LLVMGetDiagInfoSeverity(info),
+            message: LLVMString::from_raw(LLVMGetDiagInfoDescription(info)).into(),
+        }
+    }
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let severity = match self.severity {
+            LLVMDiagnosticSeverity::LLVMDSError => "error",
+            LLVMDiagnosticSeverity::LLVMDSWarning => "warning",
+            LLVMDiagnosticSeverity::LLVMDSRemark => "remark",
+            LLVMDiagnosticSeverity::LLVMDSNote => "note",
+        };
+        write!(f, "{}: {}", severity, self.message)
+    }
+}
+
+type Handler = Box<dyn FnMut(&Diagnostic)>;
+
+/// The diagnostic handlers installed in a context with
+/// [`Context::set_diagnostic_handler`], which the context owns so that they
+/// stay valid for as long as LLVM may call them. The latest one gets the
+/// diagnostics.
+pub(super) struct Handlers {
+    installed: RefCell<Vec<(u64, Handler)>>,
+    next_id: Cell<u64>,
+    /// The handler to restore once none are installed.
+    previous: Cell<(LLVMDiagnosticHandler, *mut ::libc::c_void)>,
+}
+
+impl Handlers {
+    pub(super) fn new() -> Handlers {
+        Handlers {
+            installed: RefCell::new(Vec::new()),
+            next_id: Cell::new(0),
+            previous: Cell::new((None, std::ptr::null_mut())),
+        }
+    }
+
+    /// Install `handler` in `context`, whose handlers these are, returning
+    /// the id to remove it with.
+    fn install(&self, context: LLVMContextRef, handler: Handler) -> u64 {
+        let id = self.next_id.get();
+        self.next_id.set(id + 1);
+        let mut installed = self.installed.borrow_mut();
+        if installed.is_empty() {
+            unsafe {
+                self.previous.set((
+                    LLVMContextGetDiagnosticHandler(context),
+                    LLVMContextGetDiagnosticContext(context),
+                ));
+                LLVMContextSetDiagnosticHandler(
+                    context,
+                    Some(handle_diagnostic),
+                    self as *const Handlers as *mut ::libc::c_void,
+                );
+            }
+        }
+        installed.push((id, handler));
+        id
+    }
+
+    /// Remove the handler installed as `id`, restoring the previous handler
+    /// of `context` if it was the last one.
+    fn remove(&self, context: LLVMContextRef, id: u64) {
+        let mut installed = self.installed.borrow_mut();
+        installed.retain(|&(installed_id, _)| installed_id != id);
+        if installed.is_empty() {
+            self.restore(context);
+        }
+    }
+
+    /// Remove all the handlers, restoring the previous handler of `context`
+    /// if there were any.
+    pub(super) fn clear(&self, context: LLVMContextRef) {
+        let mut installed = self.installed.borrow_mut();
+        if !installed.is_empty() {
+            installed.clear();
+            self.restore(context);
+        }
+    }
+
+    fn restore(&self, context: LLVMContextRef) {
+        let (handler, diagnostic_context) = self.previous.get();
+        unsafe { LLVMContextSetDiagnosticHandler(context, handler, diagnostic_context) }
+    }
+}
+
+impl fmt::Debug for Handlers {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Handlers")
+            .field("installed", &self.installed.try_borrow().map(|h| h.len()))
+            .finish()
+    }
+}
+
+extern "C" fn handle_diagnostic(info: LLVMDiagnosticInfoRef, handlers: *mut ::libc::c_void) {
+    let handlers = unsafe { &*(handlers as *const Handlers) };
+    let result = panic::catch_unwind(AssertUnwindSafe(|| {
+        let diagnostic = unsafe { Diagnostic::from_raw(info) };
+        // A diagnostic reported while a handler runs (because it used the
+        // context) has nowhere to go.
+        if let Ok(mut installed) = handlers.installed.try_borrow_mut() {
+            if let Some((_, handler)) = installed.last_mut() {
+                handler(&diagnostic);
+            }
+        }
+    }));
+    // The panic can't unwind through LLVM.
+    if result.is_err() {
+        process::abort();
+    }
+}
+
+/// A diagnostic handler installed with [`Context::set_diagnostic_handler`],
+/// which is removed when this is dropped.
+#[derive(Debug)]
+pub struct DiagnosticHandlerGuard<'ctx> {
+    context: &'ctx Context,
+    id: u64,
+}
+
+impl<'ctx> DiagnosticHandlerGuard<'ctx> {
+    pub(super) fn new<F>(context: &'ctx Context, handler: F) -> DiagnosticHandlerGuard<'ctx>
+    where
+        F: FnMut(&Diagnostic) + 'static,
+    {
+        let id = context
+            .diagnostic_handlers()
+            .install(context.as_raw(), Box::new(handler));
+        DiagnosticHandlerGuard { context, id }
+    }
+}
+
+impl<'ctx> Drop for DiagnosticHandlerGuard<'ctx> {
+    fn drop(&mut self) {
+        self.context
+            .diagnostic_handlers()
+            .remove(self.context.as_raw(), self.id);
+    }
+}
+
+/// Diagnostics collected from a context with
+/// [`Context::collect_diagnostics`], until this is dropped.
+#[derive(Debug)]
+pub struct DiagnosticCollector<'ctx> {
+    diagnostics: Rc<RefCell<Vec<Diagnostic>>>,
+    _guard: DiagnosticHandlerGuard<'ctx>,
+}
+
+impl<'ctx> DiagnosticCollector<'ctx> {
+    pub(super) fn new(context: &'ctx Context) -> DiagnosticCollector<'ctx> {
+        let diagnostics = Rc::new(RefCell::new(Vec::new()));
+        let collected = Rc::clone(&diagnostics);
+        let guard = DiagnosticHandlerGuard::new(context, move |diagnostic| {
+            collected.borrow_mut().push(diagnostic.clone())
+        });
+        DiagnosticCollector {
+            diagnostics,
+            _guard: guard,
+        }
+    }
+
+    /// The diagnostics collected so far.
+    pub fn diagnostics(&self) -> Vec<Diagnostic> {
+        self.diagnostics.borrow().clone()
+    }
+
+    /// The diagnostics collected since the last call, which are then
+    /// forgotten.
+    pub fn take(&self) -> Vec<Diagnostic> {
+        self.diagnostics.take()
+    }
+
+    /// Whether an error was collected (and not taken).
+    pub fn has_errors(&self) -> bool {
+        self.diagnostics
+            .borrow()
+            .iter()
+            .any(|diagnostic| diagnostic.severity == LLVMDiagnosticSeverity::LLVMDSError)
+    }
+
+    /// Stop collecting, returning the diagnostics collected.
+    pub fn finish(self) -> Vec<Diagnostic> {
+        self.diagnostics.take()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::safe::MemoryBuffer;
+
+    extern "C" fn ignore_diagnostic(_info: LLVMDiagnosticInfoRef, _context: *mut ::libc::c_void) {}
+
+    fn handler_address(context: &Context) -> Option<usize> {
+        unsafe { LLVMContextGetDiagnosticHandler(context.as_raw()) }.map(|handler| handler as usize)
+    }
+
+    #[test]
+    fn collector_captures_link_error() {
+        let context = Context::new();
+        let parse = |ir: &str| {
+            let buffer = MemoryBuffer::from_slice(ir.as_bytes(), "test.ll");
+            context.parse_ir(buffer).unwrap()
+        };
+        let mut module = parse("define void @f() { ret void }");
+        let collector = context.collect_diagnostics();
+        assert!(module.link(parse("define void @f() { ret void }")).is_err());
+        assert!(collector.has_errors());
+        let diagnostics = collector.finish();
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0].severity, LLVMDiagnosticSeverity::LLVMDSError);
+        assert!(diagnostics[0].message.contains("'f'"));
+    }
+
+    #[test]
+    fn guard_restores_previous_handler() {
+        let context = Context::new();
+        let mut marker = 0u8;
+        let marker = &mut marker as *mut u8 as *mut ::libc::c_void;
+        unsafe {
+            LLVMContextSetDiagnosticHandler(context.as_raw(), Some(ignore_diagnostic), marker);
+        }
+        let previous = handler_address(&context);
+
+        let outer = context.set_diagnostic_handler(|_| {});
+        let inner = context.collect_diagnostics();
+        assert_ne!(handler_address(&context), previous);
+        drop(inner);
+        assert_ne!(handler_address(&context), previous);
+        drop(outer);
+        assert_eq!(handler_address(&context), previous);
+        assert_eq!(
+            unsafe { LLVMContextGetDiagnosticContext(context.as_raw()) },
+            marker
+        );
+    }
+}
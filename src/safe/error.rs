@@ -0,0 +1,215 @@
+use std::error;
+use std::fmt;
+
+use super::{ErrorMessage, LLVMString};
+use crate::error::{LLVMErrorRef, LLVMErrorTypeId, LLVMGetErrorTypeId, LLVMGetStringErrorTypeId};
+#[cfg(all(LLVM_COMPONENT_LTO, LLVM_VERSION_11_OR_LOWER))]
+use crate::link_time_optimizer::llvm_lto_status_t;
+#[cfg(LLVM_COMPONENT_LTO)]
+use crate::lto::{lto_bool_t, lto_get_error_message};
+
+/// An error reported by LLVM, however it was reported.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Error {
+    kind: ErrorKind,
+    message: String,
+}
+
+/// How an [`Error`] was reported.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ErrorKind {
+    /// As an `LLVMErrorRef` of this type.
+    ErrorRef(ErrorTypeId),
+    /// As a failure (an `LLVMBool` or null) with a message, or by a wrapper
+    /// in this module.
+    Message,
+    /// As `true` from an LTO function returning `lto_bool_t`, with the
+    /// message from `lto_get_error_message`.
+    #[cfg(LLVM_COMPONENT_LTO)]
+    LtoBool,
+    /// As a failure status from one of the `link_time_optimizer` functions.
+    #[cfg(all(LLVM_COMPONENT_LTO, LLVM_VERSION_11_OR_LOWER))]
+    LtoStatus(llvm_lto_status_t),
+}
+
+/// The type of an `LLVMErrorRef`, from `LLVMGetErrorTypeId`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct ErrorTypeId(usize);
+
+impl ErrorTypeId {
+    /// The type of the errors that are just a string, as made by
+    /// `LLVMCreateStringError`.
+    pub fn string_error() -> ErrorTypeId {
+        ErrorTypeId(unsafe { LLVMGetStringErrorTypeId() } as usize)
+    }
+
+    pub fn as_raw(self) -> LLVMErrorTypeId {
+        self.0 as LLVMErrorTypeId
+    }
+}
+
+impl Error {
+    /// An error with `message` that isn't from LLVM itself.
+    pub(crate) fn new<S: Into<String>>(message: S) -> Error {
+        Error {
+            kind: ErrorKind::Message,
+            message: message.into(),
+        }
+    }
+
+    /// Consume `error`, returning `Ok` if it is success (null).
+    ///
+    /// # Safety
+    ///
+    /// `error` must be null or a valid error, which nothing else consumes.
+    pub unsafe fn check(error: LLVMErrorRef) -> Result<(), Error> {
+        if error.is_null() {
+            return Ok(());
+        }
+        Err(Error::from_error_ref(error))
+    }
+
+    /// Consume `error`, taking its type and message. This doesn't call
+    /// `LLVMConsumeError`, as `LLVMGetErrorMessage` consumes the error itself.
+    ///
+    /// # Safety
+    ///
+    /// `error` must be a valid error other than success (null), which nothing
+    /// else consumes.
+    pub unsafe fn from_error_ref(error: LLVMErrorRef) -> Error {
+        let type_id = ErrorTypeId(LLVMGetErrorTypeId(error) as usize);
+        Error {
+            kind: ErrorKind::ErrorRef(type_id),
+            message: ErrorMessage::from_error(error).into(),
+        }
+    }
+
+    /// An error with a message from one of the functions that report failure
+    /// with an `LLVMBool` (or null) and a message, such as `LLVMVerifyModule`.
+    /// The message is disposed of with `LLVMDisposeMessage`, and may be null.
+    ///
+    /// # Safety
+    ///
+    /// `message` must be null or a valid message, which nothing else disposes
+    /// of.
+    pub unsafe fn from_message(message: *mut ::libc::c_char) -> Error {
+        if message.is_null() {
+            return Error::new(String::new());
+        }
+        LLVMString::from_raw(message).into()
+    }
+
+    /// Check the result of an LTO function that returns `true` on error,
+    /// taking the message from `lto_get_error_message` if it is.
+    #[cfg(LLVM_COMPONENT_LTO)]
+    pub fn check_lto_bool(failed: lto_bool_t) -> Result<(), Error> {
+        if failed == 0 {
+            return Ok(());
+        }
+        let message = unsafe { lto_get_error_message() };
+        let message = if message.is_null() {
+            String::new()
+        } else {
+            unsafe { std::ffi::CStr::from_ptr(message) }
+                .to_string_lossy()
+                .into_owned()
+        };
+        Err(Error {
+            kind: ErrorKind::LtoBool,
+            message,
+        })
+    }
+
+    /// Check a status from one of the `link_time_optimizer` functions.
+    #[cfg(all(LLVM_COMPONENT_LTO, LLVM_VERSION_11_OR_LOWER))]
+    pub fn check_lto_status(status: llvm_lto_status_t) -> Result<(), Error> {
+        match status {
+            llvm_lto_status_t::LLVM_LTO_OPT_SUCCESS | llvm_lto_status_t::LLVM_LTO_READ_SUCCESS => {
+                Ok(())
+            }
+            status => Err(Error {
+                kind: ErrorKind::LtoStatus(status),
+                message: format!("link-time optimization failed: {:?}", status),
+            }),
+        }
+    }
+
+    pub fn kind(&self) -> ErrorKind {
+        self.kind
+    }
+
+    /// The type of the `LLVMErrorRef` the error came from, if it did.
+    pub fn error_type(&self) -> Option<ErrorTypeId> {
+        match self.kind {
+            ErrorKind::ErrorRef(type_id) => Some(type_id),
+            _ => None,
+        }
+    }
+
+    /// The message LLVM gave, which may be empty.
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+}
+
+impl From<LLVMString> for Error {
+    fn from(message: LLVMString) -> Error {
+        Error::new(message)
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.message.is_empty() {
+            f.write_str("LLVM reported an error without a message")
+        } else {
+            f.write_str(&self.message)
+        }
+    }
+}
+
+impl error::Error for Error {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::LLVMCreateMessage;
+
+    #[test]
+    fn check_success() {
+        assert_eq!(unsafe { Error::check(std::ptr::null_mut()) }, Ok(()));
+    }
+
+    #[test]
+    fn from_message_takes_message() {
+        let message = unsafe { LLVMCreateMessage(b"bad\0".as_ptr() as *const _) };
+        let error = unsafe { Error::from_message(message) };
+        assert_eq!(error.kind(), ErrorKind::Message);
+        assert_eq!(error.error_type(), None);
+        assert_eq!(error.message(), "bad");
+        assert_eq!(error.to_string(), "bad");
+    }
+
+    #[test]
+    fn from_message_null() {
+        let error = unsafe { Error::from_message(std::ptr::null_mut()) };
+        assert_eq!(error.message(), "");
+        assert_eq!(
+            error.to_string(),
+            "LLVM reported an error without a message"
+        );
+    }
+
+    #[cfg(all(LLVM_COMPONENT_ORC, LLVM_VERSION_11_OR_GREATER))]
+    #[test]
+    fn from_error_ref_consumes_error() {
+        crate::safe::with_lookup_error(|error| {
+            // Taking the message consumes the error, so nothing else may.
+            let error = unsafe { Error::check(error) }.unwrap_err();
+            let type_id = error.error_type().unwrap();
+            assert_eq!(error.kind(), ErrorKind::ErrorRef(type_id));
+            assert_ne!(type_id, ErrorTypeId::string_error());
+            assert!(error.message().contains("missing"));
+        });
+    }
+}
@@ -0,0 +1,147 @@
+use std::marker::PhantomData;
+use std::mem::{self, MaybeUninit};
+
+use super::{to_cstring, Context, Error, Module};
+use crate::execution_engine::{
+    LLVMAddModule, LLVMCreateExecutionEngineForModule, LLVMDisposeExecutionEngine,
+    LLVMExecutionEngineRef, LLVMGetFunctionAddress,
+};
+use crate::prelude::LLVMContextRef;
+
+/// An owned `LLVMExecutionEngineRef` in the context `'ctx`, disposed of when
+/// dropped, along with the modules it owns.
+#[derive(Debug)]
+pub struct ExecutionEngine<'ctx> {
+    raw: LLVMExecutionEngineRef,
+    /// The context of the modules, if known.
+    context_raw: Option<LLVMContextRef>,
+    context: PhantomData<&'ctx Context>,
+}
+
+impl<'ctx> ExecutionEngine<'ctx> {
+    /// Create an execution engine for `module`, which it takes ownership of
+    /// even if this fails. It is a JIT compiler if `LLVMLinkInMCJIT` was
+    /// called and the native target initialized, and an interpreter if
+    /// `LLVMLinkInInterpreter` was called.
+    pub fn new(module: Module<'ctx>) -> Result<ExecutionEngine<'ctx>, Error> {
+        let context_raw = module.context_raw();
+        let mut engine = MaybeUninit::uninit();
+        let mut message = std::ptr::null_mut();
+        unsafe {
+            if LLVMCreateExecutionEngineForModule(
+                engine.as_mut_ptr(),
+                module.into_raw(),
+                &mut message,
+            ) != 0
+            {
+                return Err(Error::from_message(message));
+            }
+            let mut engine = ExecutionEngine::from_raw(engine.assume_init());
+            engine.context_raw = Some(context_raw);
+            Ok(engine)
+        }
+    }
+
+    /// Take ownership of an execution engine.
+    ///
+    /// # Safety
+    ///
+    /// `raw` must be a valid execution engine in a context that outlives
+    /// `'ctx`, which nothing else disposes of. Its context isn't known, so
+    /// [`add_module`](ExecutionEngine::add_module) only checks the modules
+    /// added later against the first one of them.
+    pub unsafe fn from_raw(raw: LLVMExecutionEngineRef) -> ExecutionEngine<'ctx> {
+        ExecutionEngine {
+            raw,
+            context_raw: None,
+            context: PhantomData,
+        }
+    }
+
+    pub fn as_raw(&self) -> LLVMExecutionEngineRef {
+        self.raw
+    }
+
+    /// Give up ownership of the execution engine, without disposing of it.
+    pub fn into_raw(self) -> LLVMExecutionEngineRef {
+        let raw = self.raw;
+        mem::forget(self);
+        raw
+    }
+
+    /// Add `module` to the engine, which takes ownership of it. This fails
+    /// (dropping `module`) if it is in a different context than the engine's
+    /// other modules.
+    pub fn add_module(&mut self, module: Module<'ctx>) -> Result<(), Error> {
+        let context_raw = module.context_raw();
+        if *self.context_raw.get_or_insert(context_raw) != context_raw {
+            return Err(Error::new(
+                "cannot add a module in a different context to an execution engine",
+            ));
+        }
+        unsafe { LLVMAddModule(self.raw, module.into_raw()) };
+        Ok(())
+    }
+
+    /// The address of the compiled function called `name`, if there is one.
+    /// This compiles the modules that haven't been.
+    pub fn get_function_address(&self, name: &str) -> Option<u64> {
+        let name = to_cstring(name);
+        match unsafe { LLVMGetFunctionAddress(self.raw, name.as_ptr()) } {
+            0 => None,
+            address => Some(address),
+        }
+    }
+}
+
+impl<'ctx> Drop for ExecutionEngine<'ctx> {
+    fn drop(&mut self) {
+        unsafe { LLVMDisposeExecutionEngine(self.raw) }
+    }
+}
+
+#[cfg(all(test, LLVM_COMPONENT_MCJIT))]
+mod tests {
+    use super::*;
+    use crate::execution_engine::LLVMLinkInMCJIT;
+    use crate::safe::{ErrorKind, MemoryBuffer};
+    use crate::target::{LLVM_InitializeNativeAsmPrinter, LLVM_InitializeNativeTarget};
+
+    fn parse<'ctx>(context: &'ctx Context, ir: &str) -> Module<'ctx> {
+        let buffer = MemoryBuffer::from_slice(ir.as_bytes(), "test.ll");
+        context.parse_ir(buffer).unwrap()
+    }
+
+    fn engine<'ctx>(context: &'ctx Context) -> ExecutionEngine<'ctx> {
+        unsafe {
+            LLVMLinkInMCJIT();
+            assert_eq!(LLVM_InitializeNativeTarget(), 0);
+            assert_eq!(LLVM_InitializeNativeAsmPrinter(), 0);
+        }
+        ExecutionEngine::new(parse(context, "define i32 @f() { ret i32 1 }")).unwrap()
+    }
+
+    #[test]
+    fn add_module_transfers_ownership() {
+        let context = Context::new();
+        let mut engine = engine(&context);
+        engine
+            .add_module(parse(&context, "define i32 @g() { ret i32 2 }"))
+            .unwrap();
+        assert!(engine.get_function_address("f").is_some());
+        assert!(engine.get_function_address("g").is_some());
+        // Dropping the engine disposes of both modules, once.
+    }
+
+    #[test]
+    fn add_module_rejects_other_context() {
+        let context = Context::new();
+        let other_context = Context::new();
+        let mut engine = engine(&context);
+        let error = engine
+            .add_module(parse(&other_context, "define i32 @g() { ret i32 2 }"))
+            .unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::Message);
+        assert!(engine.get_function_address("g").is_none());
+    }
+}
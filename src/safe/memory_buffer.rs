@@ -0,0 +1,86 @@
+use std::mem::{self, MaybeUninit};
+use std::path::Path;
+use std::slice;
+
+use super::{path_to_cstring, to_cstring, Error};
+use crate::core::{
+    LLVMCreateMemoryBufferWithContentsOfFile, LLVMCreateMemoryBufferWithMemoryRangeCopy,
+    LLVMDisposeMemoryBuffer, LLVMGetBufferSize, LLVMGetBufferStart,
+};
+use crate::prelude::*;
+
+/// An owned `LLVMMemoryBufferRef`, disposed of when dropped.
+#[derive(Debug)]
+pub struct MemoryBuffer {
+    raw: LLVMMemoryBufferRef,
+}
+
+impl MemoryBuffer {
+    /// A buffer holding a copy of `data`, called `name` in messages.
+    pub fn from_slice(data: &[u8], name: &str) -> MemoryBuffer {
+        let name = to_cstring(name);
+        unsafe {
+            MemoryBuffer::from_raw(LLVMCreateMemoryBufferWithMemoryRangeCopy(
+                data.as_ptr() as *const ::libc::c_char,
+                data.len(),
+                name.as_ptr(),
+            ))
+        }
+    }
+
+    /// A buffer holding the contents of the file at `path`.
+    pub fn from_file<P: AsRef<Path>>(path: P) -> Result<MemoryBuffer, Error> {
+        let path = path_to_cstring(path.as_ref())?;
+        let mut buffer = MaybeUninit::uninit();
+        let mut message = std::ptr::null_mut();
+        unsafe {
+            if LLVMCreateMemoryBufferWithContentsOfFile(
+                path.as_ptr(),
+                buffer.as_mut_ptr(),
+                &mut message,
+            ) != 0
+            {
+                return Err(Error::from_message(message));
+            }
+            Ok(MemoryBuffer::from_raw(buffer.assume_init()))
+        }
+    }
+
+    /// Take ownership of a buffer.
+    ///
+    /// # Safety
+    ///
+    /// `raw` must be a valid buffer that nothing else disposes of.
+    pub unsafe fn from_raw(raw: LLVMMemoryBufferRef) -> MemoryBuffer {
+        MemoryBuffer { raw }
+    }
+
+    pub fn as_raw(&self) -> LLVMMemoryBufferRef {
+        self.raw
+    }
+
+    /// Give up ownership of the buffer, without disposing of it.
+    pub fn into_raw(self) -> LLVMMemoryBufferRef {
+        let raw = self.raw;
+        mem::forget(self);
+        raw
+    }
+
+    /// The contents of the buffer.
+    pub fn as_slice(&self) -> &[u8] {
+        unsafe {
+            let start = LLVMGetBufferStart(self.raw) as *const u8;
+            let size = LLVMGetBufferSize(self.raw);
+            if size == 0 {
+                return &[];
+            }
+            slice::from_raw_parts(start, size)
+        }
+    }
+}
+
+impl Drop for MemoryBuffer {
+    fn drop(&mut self) {
+        unsafe { LLVMDisposeMemoryBuffer(self.raw) }
+    }
+}
@@ -0,0 +1,126 @@
+//! Owned wrappers for the handles that have to be disposed of, for the
+//! `safe` feature.
+//!
+//! Each wrapper disposes of its handle when dropped. A [`Module`] or
+//! [`Builder`] borrows the [`Context`] it was created in, so it can't outlive
+//! it. The functions that take ownership of a handle take its wrapper by
+//! value, so it can't be used (or disposed of) again afterwards:
+//! [`Context::parse_ir`] consumes the [`MemoryBuffer`], [`Module::link`]
+//! destroys the module linked in, and an `ExecutionEngine` owns the modules
+//! it is given.
+//! Their lifetimes don't tell contexts apart, so [`Module::link`] and
+//! `ExecutionEngine::add_module` check at run time that the modules are in the
+//! same context, and fail if they aren't.
+//!
+//! Strings that LLVM allocates are returned as [`LLVMString`],
+//! [`ErrorMessage`] or `MangledSymbol`, one for each function that disposes of
+//! them.
+//!
+//! The wrappers report failures as an [`Error`], which can also be made from
+//! an `LLVMErrorRef` ([`Error::check`]), or from the message or status of the
+//! other functions that can fail. What LLVM reports only as diagnostics, such
+//! as why linking failed, can be collected with
+//! [`Context::collect_diagnostics`].
+//!
+//! Everything else is left to the bindings: `as_raw` gives the handle to pass
+//! to them, and `into_raw` gives up ownership of it.
+//!
+//! ```no_run
+//! use llvm_sys_featured::safe::{Context, MemoryBuffer};
+//!
+//! let context = Context::new();
+//! let buffer = MemoryBuffer::from_slice(b"define void @f() { ret void }", "f.ll");
+//! let mut module = context.parse_ir(buffer).unwrap();
+//! let diagnostics = context.collect_diagnostics();
+//! if module.link(context.create_module("empty")).is_err() {
+//!     panic!("{:?}", diagnostics.finish());
+//! }
+//! module.verify().unwrap();
+//! println!("{}", module.print_to_string());
+//! ```
+
+use std::ffi::CString;
+use std::path::Path;
+
+use super::core::LLVMPrintValueToString;
+use super::prelude::*;
+
+#[cfg(LLVM_VERSION_9_OR_GREATER)]
+mod binary;
+mod builder;
+mod context;
+mod diagnostics;
+mod error;
+#[cfg(any(LLVM_COMPONENT_MCJIT, LLVM_COMPONENT_INTERPRETER))]
+mod execution_engine;
+mod memory_buffer;
+mod module;
+#[cfg(all(LLVM_COMPONENT_ORC, LLVM_VERSION_11_OR_LOWER))]
+mod orc;
+mod pass_manager;
+mod string;
+mod target_machine;
+
+#[cfg(LLVM_VERSION_9_OR_GREATER)]
+pub use self::binary::Binary;
+pub use self::builder::Builder;
+pub use self::context::Context;
+pub use self::diagnostics::{Diagnostic, DiagnosticCollector, DiagnosticHandlerGuard};
+pub use self::error::{Error, ErrorKind, ErrorTypeId};
+#[cfg(any(LLVM_COMPONENT_MCJIT, LLVM_COMPONENT_INTERPRETER))]
+pub use self::execution_engine::ExecutionEngine;
+pub use self::memory_buffer::MemoryBuffer;
+pub use self::module::Module;
+#[cfg(all(LLVM_COMPONENT_ORC, LLVM_VERSION_11_OR_LOWER))]
+pub use self::orc::OrcJitStack;
+pub use self::pass_manager::{FunctionPassManager, PassManager};
+#[cfg(all(LLVM_COMPONENT_ORC, LLVM_VERSION_11_OR_LOWER))]
+pub use self::string::MangledSymbol;
+pub use self::string::{ErrorMessage, LLVMString};
+pub use self::target_machine::{
+    default_target_triple, host_cpu_features, host_cpu_name, normalize_target_triple,
+    TargetMachine,
+};
+
+/// The value as LLVM IR text.
+///
+/// # Safety
+///
+/// `value` must be a valid value.
+pub unsafe fn print_value_to_string(value: LLVMValueRef) -> LLVMString {
+    LLVMString::from_raw(LLVMPrintValueToString(value))
+}
+
+/// Convert a name to pass to LLVM, panicking if it contains a nul.
+fn to_cstring(name: &str) -> CString {
+    CString::new(name).expect("llvm-sys-featured: a name passed to LLVM contains a nul")
+}
+
+/// Convert a path to pass to LLVM, which only takes UTF-8 ones.
+fn path_to_cstring(path: &Path) -> Result<CString, Error> {
+    match path.to_str() {
+        Some(path) => Ok(to_cstring(path)),
+        None => Err(Error::new(format!("{}: path is not UTF-8", path.display()))),
+    }
+}
+
+/// Call `f` with an error from LLJIT failing to find a symbol, for the tests
+/// (`LLVMCreateStringError` is only in LLVM 13 and newer). The JIT is kept
+/// until `f` returns, as the error refers to its symbols.
+#[cfg(all(test, LLVM_COMPONENT_ORC, LLVM_VERSION_11_OR_GREATER))]
+fn with_lookup_error<F: FnOnce(crate::error::LLVMErrorRef)>(f: F) {
+    use crate::orc::{LLVMOrcCreateLLJIT, LLVMOrcDisposeLLJIT, LLVMOrcLLJITLookup};
+    use crate::target::{LLVM_InitializeNativeAsmPrinter, LLVM_InitializeNativeTarget};
+
+    unsafe {
+        assert_eq!(LLVM_InitializeNativeTarget(), 0);
+        assert_eq!(LLVM_InitializeNativeAsmPrinter(), 0);
+        let mut jit = std::ptr::null_mut();
+        Error::check(LLVMOrcCreateLLJIT(&mut jit, std::ptr::null_mut())).unwrap();
+        let mut address = 0;
+        let error = LLVMOrcLLJITLookup(jit, &mut address, b"missing\0".as_ptr() as *const _);
+        assert!(!error.is_null());
+        f(error);
+        Error::check(LLVMOrcDisposeLLJIT(jit)).unwrap();
+    }
+}
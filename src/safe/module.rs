@@ -0,0 +1,184 @@
+use std::marker::PhantomData;
+use std::mem;
+
+use super::{to_cstring, Context, Error, LLVMString, MemoryBuffer};
+use crate::analysis::{LLVMVerifierFailureAction, LLVMVerifyModule};
+use crate::bit_writer::LLVMWriteBitcodeToMemoryBuffer;
+use crate::core::{
+    LLVMCloneModule, LLVMDisposeModule, LLVMGetModuleContext, LLVMGetNamedFunction,
+    LLVMModuleCreateWithNameInContext, LLVMPrintModuleToString,
+};
+use crate::linker::LLVMLinkModules2;
+use crate::prelude::*;
+
+/// An owned `LLVMModuleRef` in the context `'ctx`, disposed of when dropped.
+#[derive(Debug)]
+pub struct Module<'ctx> {
+    raw: LLVMModuleRef,
+    context: PhantomData<&'ctx Context>,
+}
+
+impl<'ctx> Module<'ctx> {
+    /// Create an empty module in `context`.
+    pub fn new(name: &str, context: &'ctx Context) -> Module<'ctx> {
+        let name = to_cstring(name);
+        unsafe {
+            Module::from_raw(LLVMModuleCreateWithNameInContext(
+                name.as_ptr(),
+                context.as_raw(),
+            ))
+        }
+    }
+
+    /// Take ownership of a module.
+    ///
+    /// # Safety
+    ///
+    /// `raw` must be a valid module in a context that outlives `'ctx`, which
+    /// nothing else disposes of.
+    pub unsafe fn from_raw(raw: LLVMModuleRef) -> Module<'ctx> {
+        Module {
+            raw,
+            context: PhantomData,
+        }
+    }
+
+    pub fn as_raw(&self) -> LLVMModuleRef {
+        self.raw
+    }
+
+    /// Give up ownership of the module, without disposing of it.
+    pub fn into_raw(self) -> LLVMModuleRef {
+        let raw = self.raw;
+        mem::forget(self);
+        raw
+    }
+
+    /// The context the module is in.
+    pub(super) fn context_raw(&self) -> LLVMContextRef {
+        unsafe { LLVMGetModuleContext(self.raw) }
+    }
+
+    /// The function called `name`, if the module has one.
+    pub fn get_function(&self, name: &str) -> Option<LLVMValueRef> {
+        let name = to_cstring(name);
+        let function = unsafe { LLVMGetNamedFunction(self.raw, name.as_ptr()) };
+        if function.is_null() {
+            None
+        } else {
+            Some(function)
+        }
+    }
+
+    /// Link `other` into this module, which destroys `other`.
+    ///
+    /// The modules must be in the same context; if they aren't, this fails
+    /// (and `other` is dropped). The lifetimes only ensure that both contexts
+    /// outlive the modules, not that they are the same one.
+    ///
+    /// LLVM reports why linking failed only to the context's diagnostic
+    /// handler (see [`Context::collect_diagnostics`]). Without one, it prints
+    /// the error and exits the process.
+    pub fn link(&mut self, other: Module<'ctx>) -> Result<(), Error> {
+        if other.context_raw() != self.context_raw() {
+            return Err(Error::new("cannot link modules in different contexts"));
+        }
+        if unsafe { LLVMLinkModules2(self.raw, other.into_raw()) } != 0 {
+            return Err(Error::new("failed to link modules"));
+        }
+        Ok(())
+    }
+
+    /// Check that the module is valid, describing what isn't if it isn't.
+    pub fn verify(&self) -> Result<(), Error> {
+        let mut message = std::ptr::null_mut();
+        let failed = unsafe {
+            LLVMVerifyModule(
+                self.raw,
+                LLVMVerifierFailureAction::LLVMReturnStatusAction,
+                &mut message,
+            )
+        };
+        // The message is allocated either way.
+        let error = unsafe { Error::from_message(message) };
+        if failed != 0 {
+            return Err(error);
+        }
+        Ok(())
+    }
+
+    /// The module as LLVM IR text.
+    pub fn print_to_string(&self) -> LLVMString {
+        unsafe { LLVMString::from_raw(LLVMPrintModuleToString(self.raw)) }
+    }
+
+    /// The module as bitcode.
+    pub fn write_bitcode(&self) -> MemoryBuffer {
+        unsafe { MemoryBuffer::from_raw(LLVMWriteBitcodeToMemoryBuffer(self.raw)) }
+    }
+}
+
+impl<'ctx> Clone for Module<'ctx> {
+    /// A copy of the module, in the same context.
+    fn clone(&self) -> Module<'ctx> {
+        unsafe { Module::from_raw(LLVMCloneModule(self.raw)) }
+    }
+}
+
+impl<'ctx> Drop for Module<'ctx> {
+    fn drop(&mut self) {
+        unsafe { LLVMDisposeModule(self.raw) }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::safe::ErrorKind;
+
+    fn parse<'ctx>(context: &'ctx Context, ir: &str) -> Module<'ctx> {
+        let buffer = MemoryBuffer::from_slice(ir.as_bytes(), "test.ll");
+        context.parse_ir(buffer).unwrap()
+    }
+
+    #[test]
+    fn link_consumes_other() {
+        let context = Context::new();
+        let mut module = parse(&context, "define void @f() { ret void }");
+        let other = parse(&context, "define void @g() { ret void }");
+        module.link(other).unwrap();
+        assert!(module.get_function("f").is_some());
+        assert!(module.get_function("g").is_some());
+        module.verify().unwrap();
+        // Dropping the module and context here disposes of each only once:
+        // `other` was destroyed by linking, not by its own drop.
+    }
+
+    #[test]
+    fn link_rejects_other_context() {
+        let context = Context::new();
+        let other_context = Context::new();
+        let mut module = parse(&context, "define void @f() { ret void }");
+        let error = module
+            .link(parse(&other_context, "define void @g() { ret void }"))
+            .unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::Message);
+        assert!(module.get_function("g").is_none());
+    }
+
+    #[test]
+    fn clone_outlives_original() {
+        let context = Context::new();
+        let module = parse(&context, "define void @f() { ret void }");
+        let clone = module.clone();
+        drop(module);
+        assert!(clone.get_function("f").is_some());
+    }
+
+    #[test]
+    fn into_raw_gives_up_ownership() {
+        let context = Context::new();
+        let raw = context.create_module("m").into_raw();
+        unsafe { LLVMDisposeModule(raw) };
+    }
+}
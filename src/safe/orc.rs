@@ -0,0 +1,74 @@
+use std::mem;
+
+use super::{to_cstring, Error, MangledSymbol, TargetMachine};
+use crate::error::LLVMConsumeError;
+use crate::orc::{
+    LLVMOrcCreateInstance, LLVMOrcDisposeInstance, LLVMOrcGetMangledSymbol,
+    LLVMOrcGetSymbolAddress, LLVMOrcJITStackRef,
+};
+
+/// An owned `LLVMOrcJITStackRef`, disposed of when dropped.
+#[derive(Debug)]
+pub struct OrcJitStack {
+    raw: LLVMOrcJITStackRef,
+}
+
+impl OrcJitStack {
+    /// Create an ORC JIT stack, which takes ownership of `target_machine`.
+    pub fn new(target_machine: TargetMachine) -> OrcJitStack {
+        OrcJitStack {
+            raw: unsafe { LLVMOrcCreateInstance(target_machine.into_raw()) },
+        }
+    }
+
+    /// Take ownership of an ORC JIT stack.
+    ///
+    /// # Safety
+    ///
+    /// `raw` must be a valid ORC JIT stack that nothing else disposes of.
+    pub unsafe fn from_raw(raw: LLVMOrcJITStackRef) -> OrcJitStack {
+        OrcJitStack { raw }
+    }
+
+    pub fn as_raw(&self) -> LLVMOrcJITStackRef {
+        self.raw
+    }
+
+    /// Give up ownership of the ORC JIT stack, without disposing of it.
+    pub fn into_raw(self) -> LLVMOrcJITStackRef {
+        let raw = self.raw;
+        mem::forget(self);
+        raw
+    }
+
+    /// `symbol` mangled for the stack's target, such as `_f` for `f` on
+    /// macOS.
+    pub fn mangled_symbol(&self, symbol: &str) -> MangledSymbol {
+        let symbol = to_cstring(symbol);
+        let mut mangled = std::ptr::null_mut();
+        unsafe {
+            LLVMOrcGetMangledSymbol(self.raw, &mut mangled, symbol.as_ptr());
+            MangledSymbol::from_raw(mangled)
+        }
+    }
+
+    /// The address of the symbol called `name` (mangled), or 0 if there is
+    /// none. This compiles the module defining it if it hasn't been.
+    pub fn symbol_address(&self, name: &str) -> Result<u64, Error> {
+        let name = to_cstring(name);
+        let mut address = 0;
+        unsafe {
+            Error::check(LLVMOrcGetSymbolAddress(self.raw, &mut address, name.as_ptr()))?;
+        }
+        Ok(address)
+    }
+}
+
+impl Drop for OrcJitStack {
+    fn drop(&mut self) {
+        let error = unsafe { LLVMOrcDisposeInstance(self.raw) };
+        if !error.is_null() {
+            unsafe { LLVMConsumeError(error) }
+        }
+    }
+}
@@ -0,0 +1,118 @@
+use std::marker::PhantomData;
+use std::mem;
+
+use super::Module;
+use crate::core::{
+    LLVMCreateFunctionPassManagerForModule, LLVMCreatePassManager, LLVMDisposePassManager,
+    LLVMFinalizeFunctionPassManager, LLVMInitializeFunctionPassManager,
+    LLVMRunFunctionPassManager, LLVMRunPassManager,
+};
+use crate::prelude::*;
+
+/// An owned `LLVMPassManagerRef` running passes on whole modules, disposed of
+/// when dropped.
+///
+/// The `LLVMAdd*Pass` functions in `transforms` take
+/// [`as_raw`](PassManager::as_raw).
+#[derive(Debug)]
+pub struct PassManager {
+    raw: LLVMPassManagerRef,
+}
+
+impl PassManager {
+    /// Create a pass manager with no passes.
+    pub fn new() -> PassManager {
+        PassManager {
+            raw: unsafe { LLVMCreatePassManager() },
+        }
+    }
+
+    /// Take ownership of a module pass manager.
+    ///
+    /// # Safety
+    ///
+    /// `raw` must be a valid module pass manager that nothing else disposes
+    /// of.
+    pub unsafe fn from_raw(raw: LLVMPassManagerRef) -> PassManager {
+        PassManager { raw }
+    }
+
+    pub fn as_raw(&self) -> LLVMPassManagerRef {
+        self.raw
+    }
+
+    /// Give up ownership of the pass manager, without disposing of it.
+    pub fn into_raw(self) -> LLVMPassManagerRef {
+        let raw = self.raw;
+        mem::forget(self);
+        raw
+    }
+
+    /// Run the passes on `module`, returning whether they changed it.
+    pub fn run(&self, module: &mut Module<'_>) -> bool {
+        unsafe { LLVMRunPassManager(self.raw, module.as_raw()) != 0 }
+    }
+}
+
+impl Default for PassManager {
+    fn default() -> PassManager {
+        PassManager::new()
+    }
+}
+
+impl Drop for PassManager {
+    fn drop(&mut self) {
+        unsafe { LLVMDisposePassManager(self.raw) }
+    }
+}
+
+/// An owned `LLVMPassManagerRef` running passes on the functions of the
+/// module it borrows, disposed of when dropped.
+#[derive(Debug)]
+pub struct FunctionPassManager<'m> {
+    raw: LLVMPassManagerRef,
+    module: PhantomData<&'m Module<'m>>,
+}
+
+impl<'m> FunctionPassManager<'m> {
+    /// Create a pass manager with no passes, for the functions of `module`.
+    pub fn new(module: &'m Module<'_>) -> FunctionPassManager<'m> {
+        FunctionPassManager {
+            raw: unsafe { LLVMCreateFunctionPassManagerForModule(module.as_raw()) },
+            module: PhantomData,
+        }
+    }
+
+    pub fn as_raw(&self) -> LLVMPassManagerRef {
+        self.raw
+    }
+
+    /// Run the initialization of the passes, returning whether it changed the
+    /// module. Call this after adding the passes and before [`run`].
+    ///
+    /// [`run`]: FunctionPassManager::run
+    pub fn initialize(&self) -> bool {
+        unsafe { LLVMInitializeFunctionPassManager(self.raw) != 0 }
+    }
+
+    /// Run the passes on `function`, returning whether they changed it.
+    ///
+    /// # Safety
+    ///
+    /// `function` must be a function in the module.
+    pub unsafe fn run(&self, function: LLVMValueRef) -> bool {
+        LLVMRunFunctionPassManager(self.raw, function) != 0
+    }
+
+    /// Run the finalization of the passes, returning whether it changed the
+    /// module.
+    pub fn finalize(&self) -> bool {
+        unsafe { LLVMFinalizeFunctionPassManager(self.raw) != 0 }
+    }
+}
+
+impl<'m> Drop for FunctionPassManager<'m> {
+    fn drop(&mut self) {
+        unsafe { LLVMDisposePassManager(self.raw) }
+    }
+}
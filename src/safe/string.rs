@@ -0,0 +1,144 @@
+use std::ffi::CStr;
+use std::fmt;
+use std::mem;
+use std::ops::Deref;
+
+use crate::core::LLVMDisposeMessage;
+use crate::error::{LLVMDisposeErrorMessage, LLVMErrorRef, LLVMGetErrorMessage};
+#[cfg(all(LLVM_COMPONENT_ORC, LLVM_VERSION_11_OR_LOWER))]
+use crate::orc::LLVMOrcDisposeMangledSymbol;
+
+/// Define an owned string allocated by LLVM, which is disposed of with
+/// `$dispose`.
+macro_rules! owned_string {
+    ($(#[$attr:meta])* $name:ident, $dispose:ident) => {
+        $(#[$attr])*
+        pub struct $name {
+            raw: *mut ::libc::c_char,
+        }
+
+        impl $name {
+            /// Take ownership of a string.
+            ///
+            /// # Safety
+            ///
+            #[doc = concat!("`raw` must be a non-null string to be disposed of with `", stringify!($dispose), "`,")]
+            /// which nothing else disposes of.
+            pub unsafe fn from_raw(raw: *mut ::libc::c_char) -> $name {
+                $name { raw }
+            }
+
+            /// Give up ownership of the string, without disposing of it.
+            pub fn into_raw(self) -> *mut ::libc::c_char {
+                let raw = self.raw;
+                mem::forget(self);
+                raw
+            }
+        }
+
+        impl Deref for $name {
+            type Target = CStr;
+
+            fn deref(&self) -> &CStr {
+                unsafe { CStr::from_ptr(self.raw) }
+            }
+        }
+
+        impl fmt::Display for $name {
+            /// The string, with anything that isn't UTF-8 replaced.
+            fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                f.write_str(&self.to_string_lossy())
+            }
+        }
+
+        impl fmt::Debug for $name {
+            fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+                fmt::Debug::fmt(&**self, f)
+            }
+        }
+
+        impl From<$name> for String {
+            /// Copy the string, replacing anything that isn't UTF-8.
+            fn from(string: $name) -> String {
+                string.to_string_lossy().into_owned()
+            }
+        }
+
+        impl Drop for $name {
+            fn drop(&mut self) {
+                unsafe { $dispose(self.raw) }
+            }
+        }
+    };
+}
+
+owned_string! {
+    /// A string allocated by LLVM that is disposed of with
+    /// `LLVMDisposeMessage`, such as a message or the result of one of the
+    /// `LLVMPrint*ToString` functions.
+    LLVMString,
+    LLVMDisposeMessage
+}
+
+owned_string! {
+    /// The message of an `LLVMErrorRef`, disposed of with
+    /// `LLVMDisposeErrorMessage`.
+    ErrorMessage,
+    LLVMDisposeErrorMessage
+}
+
+#[cfg(all(LLVM_COMPONENT_ORC, LLVM_VERSION_11_OR_LOWER))]
+owned_string! {
+    /// A symbol mangled by an ORC JIT stack, disposed of with
+    /// `LLVMOrcDisposeMangledSymbol`.
+    MangledSymbol,
+    LLVMOrcDisposeMangledSymbol
+}
+
+impl ErrorMessage {
+    /// Take the message of `error`, which this consumes.
+    ///
+    /// # Safety
+    ///
+    /// `error` must be a valid error other than success (null), which nothing
+    /// else consumes.
+    pub unsafe fn from_error(error: LLVMErrorRef) -> ErrorMessage {
+        ErrorMessage::from_raw(LLVMGetErrorMessage(error))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::LLVMCreateMessage;
+
+    fn message(text: &str) -> LLVMString {
+        let text = std::ffi::CString::new(text).unwrap();
+        unsafe { LLVMString::from_raw(LLVMCreateMessage(text.as_ptr())) }
+    }
+
+    #[test]
+    fn llvm_string_reads_and_drops() {
+        let string = message("hello");
+        assert_eq!(string.to_bytes(), b"hello");
+        assert_eq!(string.to_string(), "hello");
+        assert_eq!(format!("{:?}", string), "\"hello\"");
+        // Converting copies the string and disposes of the original.
+        assert_eq!(String::from(string), "hello");
+    }
+
+    #[test]
+    fn llvm_string_into_raw_gives_up_ownership() {
+        let raw = message("hello").into_raw();
+        unsafe { LLVMDisposeMessage(raw) };
+    }
+
+    #[cfg(all(LLVM_COMPONENT_ORC, LLVM_VERSION_11_OR_GREATER))]
+    #[test]
+    fn error_message_consumes_error_and_drops() {
+        crate::safe::with_lookup_error(|error| {
+            let message = unsafe { ErrorMessage::from_error(error) };
+            assert!(message.to_string().contains("missing"));
+        });
+    }
+}
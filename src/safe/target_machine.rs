@@ -0,0 +1,167 @@
+use std::mem::{self, MaybeUninit};
+use std::path::Path;
+
+use super::{path_to_cstring, to_cstring, Error, LLVMString, MemoryBuffer, Module};
+use crate::target_machine::{
+    LLVMCodeGenFileType, LLVMCodeGenOptLevel, LLVMCodeModel, LLVMCreateTargetMachine,
+    LLVMDisposeTargetMachine, LLVMGetDefaultTargetTriple, LLVMGetHostCPUFeatures,
+    LLVMGetHostCPUName, LLVMGetTargetFromTriple, LLVMGetTargetMachineCPU,
+    LLVMGetTargetMachineFeatureString, LLVMGetTargetMachineTriple, LLVMNormalizeTargetTriple,
+    LLVMRelocMode, LLVMTargetMachineEmitToFile, LLVMTargetMachineEmitToMemoryBuffer,
+    LLVMTargetMachineRef,
+};
+
+/// An owned `LLVMTargetMachineRef`, disposed of when dropped.
+#[derive(Debug)]
+pub struct TargetMachine {
+    raw: LLVMTargetMachineRef,
+}
+
+impl TargetMachine {
+    /// Create a target machine for `triple`, whose target must have been
+    /// initialized (see the `LLVM_Initialize*` functions in `target`).
+    pub fn new(
+        triple: &str,
+        cpu: &str,
+        features: &str,
+        level: LLVMCodeGenOptLevel,
+        reloc: LLVMRelocMode,
+        code_model: LLVMCodeModel,
+    ) -> Result<TargetMachine, Error> {
+        let triple = to_cstring(triple);
+        let cpu = to_cstring(cpu);
+        let features = to_cstring(features);
+        let mut target = MaybeUninit::uninit();
+        let mut message = std::ptr::null_mut();
+        unsafe {
+            if LLVMGetTargetFromTriple(triple.as_ptr(), target.as_mut_ptr(), &mut message) != 0 {
+                return Err(Error::from_message(message));
+            }
+            let raw = LLVMCreateTargetMachine(
+                target.assume_init(),
+                triple.as_ptr(),
+                cpu.as_ptr(),
+                features.as_ptr(),
+                level,
+                reloc,
+                code_model,
+            );
+            if raw.is_null() {
+                return Err(Error::new(format!(
+                    "no target machine for {}",
+                    triple.to_string_lossy()
+                )));
+            }
+            Ok(TargetMachine::from_raw(raw))
+        }
+    }
+
+    /// Take ownership of a target machine.
+    ///
+    /// # Safety
+    ///
+    /// `raw` must be a valid target machine that nothing else disposes of.
+    pub unsafe fn from_raw(raw: LLVMTargetMachineRef) -> TargetMachine {
+        TargetMachine { raw }
+    }
+
+    pub fn as_raw(&self) -> LLVMTargetMachineRef {
+        self.raw
+    }
+
+    /// Give up ownership of the target machine, without disposing of it.
+    pub fn into_raw(self) -> LLVMTargetMachineRef {
+        let raw = self.raw;
+        mem::forget(self);
+        raw
+    }
+
+    pub fn triple(&self) -> LLVMString {
+        unsafe { LLVMString::from_raw(LLVMGetTargetMachineTriple(self.raw)) }
+    }
+
+    pub fn cpu(&self) -> LLVMString {
+        unsafe { LLVMString::from_raw(LLVMGetTargetMachineCPU(self.raw)) }
+    }
+
+    pub fn feature_string(&self) -> LLVMString {
+        unsafe { LLVMString::from_raw(LLVMGetTargetMachineFeatureString(self.raw)) }
+    }
+
+    /// Compile `module` to an assembly or object file at `path`. Code
+    /// generation may change the module's IR.
+    pub fn emit_to_file<P: AsRef<Path>>(
+        &self,
+        module: &mut Module<'_>,
+        path: P,
+        file_type: LLVMCodeGenFileType,
+    ) -> Result<(), Error> {
+        let path = path_to_cstring(path.as_ref())?;
+        let mut message = std::ptr::null_mut();
+        unsafe {
+            if LLVMTargetMachineEmitToFile(
+                self.raw,
+                module.as_raw(),
+                path.as_ptr() as *mut ::libc::c_char,
+                file_type,
+                &mut message,
+            ) != 0
+            {
+                return Err(Error::from_message(message));
+            }
+        }
+        Ok(())
+    }
+
+    /// Compile `module` to assembly or an object file in memory. Code
+    /// generation may change the module's IR.
+    pub fn emit_to_memory_buffer(
+        &self,
+        module: &mut Module<'_>,
+        file_type: LLVMCodeGenFileType,
+    ) -> Result<MemoryBuffer, Error> {
+        let mut buffer = MaybeUninit::uninit();
+        let mut message = std::ptr::null_mut();
+        unsafe {
+            if LLVMTargetMachineEmitToMemoryBuffer(
+                self.raw,
+                module.as_raw(),
+                file_type,
+                &mut message,
+                buffer.as_mut_ptr(),
+            ) != 0
+            {
+                return Err(Error::from_message(message));
+            }
+            Ok(MemoryBuffer::from_raw(buffer.assume_init()))
+        }
+    }
+}
+
+impl Drop for TargetMachine {
+    fn drop(&mut self) {
+        unsafe { LLVMDisposeTargetMachine(self.raw) }
+    }
+}
+
+/// The triple LLVM generates code for by default, normally the host's.
+pub fn default_target_triple() -> LLVMString {
+    unsafe { LLVMString::from_raw(LLVMGetDefaultTargetTriple()) }
+}
+
+/// `triple` in its canonical form, such as `x86_64-unknown-linux-gnu` for
+/// `x86_64-linux-gnu`.
+pub fn normalize_target_triple(triple: &str) -> LLVMString {
+    let triple = to_cstring(triple);
+    unsafe { LLVMString::from_raw(LLVMNormalizeTargetTriple(triple.as_ptr())) }
+}
+
+/// The name of the host's CPU, such as `skylake`.
+pub fn host_cpu_name() -> LLVMString {
+    unsafe { LLVMString::from_raw(LLVMGetHostCPUName()) }
+}
+
+/// The features of the host's CPU, such as `+sse2,-avx512f,...`.
+pub fn host_cpu_features() -> LLVMString {
+    unsafe { LLVMString::from_raw(LLVMGetHostCPUFeatures()) }
+}
@@ -1,6 +1,6 @@
 use super::prelude::*;
 
-extern "C" {
+extern_c! {
     pub fn LLVMLoadLibraryPermanently(Filename: *const ::libc::c_char) -> LLVMBool;
     pub fn LLVMParseCommandLineOptions(
         argc: ::libc::c_int,
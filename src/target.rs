@@ -19,110 +19,156 @@ pub enum LLVMOpaqueTargetLibraryInfotData {}
 
 pub type LLVMTargetLibraryInfoRef = *mut LLVMOpaqueTargetLibraryInfotData;
 
-extern "C" {
+#[cfg(LLVM_TARGET_AMDGPU)]
+extern_c! {
     pub fn LLVMInitializeAMDGPUTargetInfo();
     pub fn LLVMInitializeAMDGPUTarget();
     pub fn LLVMInitializeAMDGPUTargetMC();
     pub fn LLVMInitializeAMDGPUAsmPrinter();
     pub fn LLVMInitializeAMDGPUAsmParser();
     // Disassembler?
+}
 
+#[cfg(LLVM_TARGET_SYSTEMZ)]
+extern_c! {
     pub fn LLVMInitializeSystemZTargetInfo();
     pub fn LLVMInitializeSystemZTarget();
     pub fn LLVMInitializeSystemZTargetMC();
     pub fn LLVMInitializeSystemZAsmPrinter();
     pub fn LLVMInitializeSystemZAsmParser();
     pub fn LLVMInitializeSystemZDisassembler();
+}
 
+#[cfg(LLVM_TARGET_HEXAGON)]
+extern_c! {
     pub fn LLVMInitializeHexagonTargetInfo();
     pub fn LLVMInitializeHexagonTarget();
     pub fn LLVMInitializeHexagonTargetMC();
     pub fn LLVMInitializeHexagonAsmPrinter();
     // AsmParser?
     pub fn LLVMInitializeHexagonDisassembler();
+}
 
+#[cfg(LLVM_TARGET_NVPTX)]
+extern_c! {
     pub fn LLVMInitializeNVPTXTargetInfo();
     pub fn LLVMInitializeNVPTXTarget();
     pub fn LLVMInitializeNVPTXTargetMC();
     pub fn LLVMInitializeNVPTXAsmPrinter();
     // AsmParser?
+}
 
+#[cfg(LLVM_TARGET_MSP430)]
+extern_c! {
     pub fn LLVMInitializeMSP430TargetInfo();
     pub fn LLVMInitializeMSP430Target();
     pub fn LLVMInitializeMSP430TargetMC();
     pub fn LLVMInitializeMSP430AsmPrinter();
     // AsmParser?
+}
 
+#[cfg(LLVM_TARGET_XCORE)]
+extern_c! {
     pub fn LLVMInitializeXCoreTargetInfo();
     pub fn LLVMInitializeXCoreTarget();
     pub fn LLVMInitializeXCoreTargetMC();
     pub fn LLVMInitializeXCoreAsmPrinter();
     // AsmParser?
     pub fn LLVMInitializeXCoreDisassembler();
+}
 
+#[cfg(LLVM_TARGET_MIPS)]
+extern_c! {
     pub fn LLVMInitializeMipsTargetInfo();
     pub fn LLVMInitializeMipsTarget();
     pub fn LLVMInitializeMipsTargetMC();
     pub fn LLVMInitializeMipsAsmPrinter();
     pub fn LLVMInitializeMipsAsmParser();
     pub fn LLVMInitializeMipsDisassembler();
+}
 
+#[cfg(LLVM_TARGET_AARCH64)]
+extern_c! {
     pub fn LLVMInitializeAArch64TargetInfo();
     pub fn LLVMInitializeAArch64Target();
     pub fn LLVMInitializeAArch64TargetMC();
     pub fn LLVMInitializeAArch64AsmPrinter();
     pub fn LLVMInitializeAArch64AsmParser();
     pub fn LLVMInitializeAArch64Disassembler();
+}
 
+#[cfg(LLVM_TARGET_ARM)]
+extern_c! {
     pub fn LLVMInitializeARMTargetInfo();
     pub fn LLVMInitializeARMTarget();
     pub fn LLVMInitializeARMTargetMC();
     pub fn LLVMInitializeARMAsmPrinter();
     pub fn LLVMInitializeARMAsmParser();
     pub fn LLVMInitializeARMDisassembler();
+}
 
+#[cfg(LLVM_TARGET_POWERPC)]
+extern_c! {
     pub fn LLVMInitializePowerPCTargetInfo();
     pub fn LLVMInitializePowerPCTarget();
     pub fn LLVMInitializePowerPCTargetMC();
     pub fn LLVMInitializePowerPCAsmPrinter();
     pub fn LLVMInitializePowerPCAsmParser();
     pub fn LLVMInitializePowerPCDisassembler();
+}
 
+#[cfg(LLVM_TARGET_SPARC)]
+extern_c! {
     pub fn LLVMInitializeSparcTargetInfo();
     pub fn LLVMInitializeSparcTarget();
     pub fn LLVMInitializeSparcTargetMC();
     pub fn LLVMInitializeSparcAsmPrinter();
     pub fn LLVMInitializeSparcAsmParser();
     pub fn LLVMInitializeSparcDisassembler();
+}
 
+#[cfg(LLVM_TARGET_X86)]
+extern_c! {
     pub fn LLVMInitializeX86TargetInfo();
     pub fn LLVMInitializeX86Target();
     pub fn LLVMInitializeX86TargetMC();
     pub fn LLVMInitializeX86AsmPrinter();
     pub fn LLVMInitializeX86AsmParser();
     pub fn LLVMInitializeX86Disassembler();
+}
 
+#[cfg(LLVM_TARGET_BPF)]
+extern_c! {
     pub fn LLVMInitializeBPFTargetInfo();
     pub fn LLVMInitializeBPFTarget();
     pub fn LLVMInitializeBPFTargetMC();
     pub fn LLVMInitializeBPFAsmPrinter();
     // No AsmParser
     pub fn LLVMInitializeBPFDisassembler();
+}
 
+#[cfg(LLVM_TARGET_LANAI)]
+extern_c! {
     pub fn LLVMInitializeLanaiTargetInfo();
     pub fn LLVMInitializeLanaiTarget();
     pub fn LLVMInitializeLanaiTargetMC();
     pub fn LLVMInitializeLanaiAsmPrinter();
     pub fn LLVMInitializeLanaiAsmParser();
     pub fn LLVMInitializeLanaiDisassembler();
+}
 
+#[cfg(LLVM_TARGET_RISCV)]
+extern_c! {
     pub fn LLVMInitializeRISCVTargetInfo();
     pub fn LLVMInitializeRISCVTarget();
     pub fn LLVMInitializeRISCVTargetMC();
     // No AsmPrinter
     // No AsmParser
     // No Disassembler
+}
 
+#[cfg(LLVM_TARGET_WEBASSEMBLY)]
+extern_c! {
     pub fn LLVMInitializeWebAssemblyTargetInfo();
     pub fn LLVMInitializeWebAssemblyTarget();
     pub fn LLVMInitializeWebAssemblyTargetMC();
@@ -131,7 +177,7 @@ extern "C" {
     pub fn LLVMInitializeWebAssemblyDisassembler();
 }
 
-extern "C" {
+extern_c! {
     /// Get the data layout for a module.
     pub fn LLVMGetModuleDataLayout(M: LLVMModuleRef) -> LLVMTargetDataRef;
     /// Set the data layout for a module.
@@ -176,6 +222,7 @@ extern "C" {
 
 // Functions from our target wrappers, since the C interface defines them with
 // macros (wrappers/target.c).
+#[cfg(not(LLVM_SYS_RUNTIME_LOADING))]
 extern "C" {
     pub fn LLVM_InitializeAllTargetInfos();
     pub fn LLVM_InitializeAllTargets();
@@ -193,3 +240,52 @@ extern "C" {
     /// Returns 1 on failure.
     pub fn LLVM_InitializeNativeDisassembler() -> LLVMBool;
 }
+
+// When LLVM is loaded at run time there are no wrappers, so the same functions
+// initialize whichever targets the loaded library was built with. Like the
+// other functions then, they are Rust functions, which panic if LLVM isn't
+// loaded.
+#[cfg(LLVM_SYS_RUNTIME_LOADING)]
+pub use self::loaded::*;
+
+#[cfg(LLVM_SYS_RUNTIME_LOADING)]
+mod loaded {
+    use super::super::prelude::*;
+    use crate::runtime::{initialize_all, initialize_native};
+
+    pub unsafe fn LLVM_InitializeAllTargetInfos() {
+        initialize_all("TargetInfo");
+    }
+    pub unsafe fn LLVM_InitializeAllTargets() {
+        initialize_all("Target");
+    }
+    pub unsafe fn LLVM_InitializeAllTargetMCs() {
+        initialize_all("TargetMC");
+    }
+    pub unsafe fn LLVM_InitializeAllAsmPrinters() {
+        initialize_all("AsmPrinter");
+    }
+    pub unsafe fn LLVM_InitializeAllAsmParsers() {
+        initialize_all("AsmParser");
+    }
+    pub unsafe fn LLVM_InitializeAllDisassemblers() {
+        initialize_all("Disassembler");
+    }
+
+    /// Returns 1 on failure.
+    pub unsafe fn LLVM_InitializeNativeTarget() -> LLVMBool {
+        initialize_native(&["TargetInfo", "Target", "TargetMC"])
+    }
+    /// Returns 1 on failure.
+    pub unsafe fn LLVM_InitializeNativeAsmParser() -> LLVMBool {
+        initialize_native(&["AsmParser"])
+    }
+    /// Returns 1 on failure.
+    pub unsafe fn LLVM_InitializeNativeAsmPrinter() -> LLVMBool {
+        initialize_native(&["AsmPrinter"])
+    }
+    /// Returns 1 on failure.
+    pub unsafe fn LLVM_InitializeNativeDisassembler() -> LLVMBool {
+        initialize_native(&["Disassembler"])
+    }
+}
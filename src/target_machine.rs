@@ -53,7 +53,7 @@ pub enum LLVMCodeGenFileType {
     LLVMObjectFile = 1,
 }
 
-extern "C" {
+extern_c! {
     pub fn LLVMGetFirstTarget() -> LLVMTargetRef;
     pub fn LLVMGetNextTarget(T: LLVMTargetRef) -> LLVMTargetRef;
     pub fn LLVMGetTargetFromName(Name: *const ::libc::c_char) -> LLVMTargetRef;
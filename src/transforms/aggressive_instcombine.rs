@@ -1,5 +1,5 @@
 use crate::prelude::*;
 
-extern "C" {
+extern_c! {
     pub fn LLVMAddAggressiveInstCombinerPass(PM: LLVMPassManagerRef);
 }
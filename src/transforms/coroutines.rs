@@ -1,6 +1,6 @@
 use crate::prelude::*;
 
-extern "C" {
+extern_c! {
     pub fn LLVMAddCoroEarlyPass(PM: LLVMPassManagerRef);
     pub fn LLVMAddCoroSplitPass(PM: LLVMPassManagerRef);
     pub fn LLVMAddCoroElidePass(PM: LLVMPassManagerRef);
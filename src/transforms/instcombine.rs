@@ -1,5 +1,5 @@
 use super::super::prelude::*;
 
-extern "C" {
+extern_c! {
     pub fn LLVMAddInstructionCombiningPass(PM: LLVMPassManagerRef);
 }
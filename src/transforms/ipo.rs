@@ -2,7 +2,7 @@
 
 use super::super::prelude::*;
 
-extern "C" {
+extern_c! {
     pub fn LLVMAddArgumentPromotionPass(PM: LLVMPassManagerRef);
     pub fn LLVMAddConstantMergePass(PM: LLVMPassManagerRef);
     #[cfg(LLVM_VERSION_10_OR_GREATER)]
@@ -14,6 +14,7 @@ extern "C" {
     pub fn LLVMAddAlwaysInlinerPass(PM: LLVMPassManagerRef);
     pub fn LLVMAddGlobalDCEPass(PM: LLVMPassManagerRef);
     pub fn LLVMAddGlobalOptimizerPass(PM: LLVMPassManagerRef);
+    #[cfg(LLVM_VERSION_11_OR_LOWER)]
     pub fn LLVMAddIPConstantPropagationPass(PM: LLVMPassManagerRef);
     pub fn LLVMAddPruneEHPass(PM: LLVMPassManagerRef);
     pub fn LLVMAddIPSCCPPass(PM: LLVMPassManagerRef);
@@ -5,7 +5,7 @@ pub enum LLVMOpaquePassManagerBuilder {}
 
 pub type LLVMPassManagerBuilderRef = *mut LLVMOpaquePassManagerBuilder;
 
-extern "C" {
+extern_c! {
     pub fn LLVMPassManagerBuilderCreate() -> LLVMPassManagerBuilderRef;
     pub fn LLVMPassManagerBuilderDispose(PMB: LLVMPassManagerBuilderRef);
     pub fn LLVMPassManagerBuilderSetOptLevel(
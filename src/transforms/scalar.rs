@@ -2,7 +2,7 @@
 
 use super::super::prelude::*;
 
-extern "C" {
+extern_c! {
     pub fn LLVMAddAggressiveDCEPass(PM: LLVMPassManagerRef);
     #[cfg(LLVM_VERSION_10_OR_GREATER)]
     pub fn LLVMAddDCEPass(PM: LLVMPassManagerRef);
@@ -38,6 +38,7 @@ extern "C" {
     );
     pub fn LLVMAddSimplifyLibCallsPass(PM: LLVMPassManagerRef);
     pub fn LLVMAddTailCallEliminationPass(PM: LLVMPassManagerRef);
+    #[cfg(LLVM_VERSION_11_OR_LOWER)]
     pub fn LLVMAddConstantPropagationPass(PM: LLVMPassManagerRef);
     pub fn LLVMAddDemoteMemoryToRegisterPass(PM: LLVMPassManagerRef);
     pub fn LLVMAddVerifierPass(PM: LLVMPassManagerRef);
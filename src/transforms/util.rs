@@ -1,6 +1,6 @@
 use super::super::prelude::*;
 
-extern "C" {
+extern_c! {
     pub fn LLVMAddLowerSwitchPass(PM: LLVMPassManagerRef);
 
     pub fn LLVMAddPromoteMemoryToRegisterPass(PM: LLVMPassManagerRef);
@@ -2,7 +2,7 @@
 
 use super::super::prelude::*;
 
-extern "C" {
+extern_c! {
     pub fn LLVMAddLoopVectorizePass(PM: LLVMPassManagerRef);
     pub fn LLVMAddSLPVectorizePass(PM: LLVMPassManagerRef);
 }
@@ -0,0 +1,215 @@
+//! The version of LLVM in use.
+//!
+//! The bindings are declared for the LLVM version selected with an `llvm-N`
+//! feature. Without `strict-versioning`, the LLVM found at build time can be a
+//! newer one, and with a shared library (or `runtime-loading`) the LLVM used at
+//! run time can be yet another. Functions whose signatures changed in between
+//! then misbehave or crash. Calling [`check`] once, before anything else, finds
+//! such a mismatch and reports it instead. With the `check-version` feature
+//! this happens automatically: `runtime::load` fails on a mismatch, and
+//! otherwise creating the first `safe::Context` panics with it.
+//!
+//! ```no_run
+//! if let Err(mismatch) = llvm_sys_featured::version::check() {
+//!     panic!("{}", mismatch);
+//! }
+//! ```
+
+use std::error::Error;
+use std::ffi::CStr;
+use std::fmt;
+
+/// An LLVM version.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Version {
+    pub major: u32,
+    pub minor: u32,
+    pub patch: u32,
+}
+
+impl Version {
+    /// Parse a version like `10.0.1`, ignoring any suffix (`10.0.1git`).
+    /// Missing components are 0.
+    fn parse(version: &str) -> Option<Version> {
+        let mut components = version.split('.').map(|component| {
+            let digits = component
+                .find(|c: char| !c.is_ascii_digit())
+                .unwrap_or(component.len());
+            component[..digits].parse::<u32>().ok()
+        });
+        let major = components.next()??;
+        let minor = components.next().flatten().unwrap_or(0);
+        let patch = components.next().flatten().unwrap_or(0);
+        Some(Version {
+            major,
+            minor,
+            patch,
+        })
+    }
+}
+
+impl fmt::Display for Version {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}.{}.{}", self.major, self.minor, self.patch)
+    }
+}
+
+/// The version selected with an `llvm-N` feature, which the bindings are
+/// declared for.
+pub fn selected() -> Version {
+    Version::parse(env!("LLVM_SYS_FEATURED_SELECTED_VERSION")).unwrap()
+}
+
+/// The version of the LLVM found at build time, if one was looked for.
+pub fn build_time() -> Option<Version> {
+    option_env!("LLVM_SYS_FEATURED_LLVM_VERSION").and_then(Version::parse)
+}
+
+/// The version of the LLVM in use, if it can be determined.
+///
+/// When LLVM is linked statically, this is the version found at build time.
+/// Otherwise it comes from the shared library: from `LLVMGetVersion` if it has
+/// it (LLVM 16 and newer), or else from the library's file name (such as
+/// `libLLVM-12.so`, where the minor and patch versions are 0 unless the name
+/// has them). `lto_get_version` isn't used, as it is in `libLTO` rather than
+/// the main LLVM library.
+///
+/// So only the major and minor versions can be relied on; [`check`] compares
+/// just those.
+pub fn runtime_version() -> Option<Version> {
+    if cfg!(not(any(
+        LLVM_LINK_SHARED,
+        LLVM_SYS_RUNTIME_LOADING,
+        feature = "no-llvm-linking"
+    ))) {
+        return build_time();
+    }
+    library_version(&symbol)
+}
+
+/// An LLVM whose version differs from the selected one, found by [`check`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct VersionMismatch {
+    /// The version the bindings are declared for
+    pub selected: Version,
+    /// The version of the LLVM in use
+    pub running: Version,
+}
+
+impl fmt::Display for VersionMismatch {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "llvm-sys-featured was compiled for LLVM {}.{}, but LLVM {}.{} is in use",
+            self.selected.major, self.selected.minor, self.running.major, self.running.minor
+        )
+    }
+}
+
+impl Error for VersionMismatch {}
+
+/// Check that the major and minor version of the LLVM in use are the selected
+/// ones, which the bindings are declared for. The patch version isn't
+/// compared, as it often can't be determined, and this passes if the version
+/// of the LLVM in use can't be determined at all (see [`runtime_version`]).
+///
+/// With `runtime-loading`, call this after `runtime::load`.
+pub fn check() -> Result<(), VersionMismatch> {
+    compare(runtime_version())
+}
+
+/// [`check`] the version of a library that `runtime::load` is loading, in
+/// which `symbol` finds the address of a function, for the `check-version`
+/// feature.
+#[cfg(all(feature = "check-version", LLVM_SYS_RUNTIME_LOADING))]
+pub(crate) fn check_library(symbol: &dyn Fn(&str) -> Option<usize>) -> Result<(), VersionMismatch> {
+    compare(library_version(symbol))
+}
+
+/// [`check`] the first time this is called, panicking on a mismatch, for the
+/// `check-version` feature.
+#[cfg(all(feature = "check-version", feature = "safe"))]
+pub(crate) fn check_once() {
+    static CHECK: std::sync::Once = std::sync::Once::new();
+    CHECK.call_once(|| {
+        if let Err(mismatch) = check() {
+            panic!("{}", mismatch);
+        }
+    });
+}
+
+fn compare(running: Option<Version>) -> Result<(), VersionMismatch> {
+    let selected = selected();
+    match running {
+        Some(running) if (running.major, running.minor) != (selected.major, selected.minor) => {
+            Err(VersionMismatch { selected, running })
+        }
+        _ => Ok(()),
+    }
+}
+
+/// The version of the shared library that `LLVMContextCreate` is in, given
+/// the function finding addresses in it.
+#[cfg(unix)]
+fn library_version(symbol: &dyn Fn(&str) -> Option<usize>) -> Option<Version> {
+    type GetVersion = unsafe extern "C" fn(*mut u32, *mut u32, *mut u32);
+
+    if let Some(address) = symbol("LLVMGetVersion\0") {
+        let get_version = unsafe { std::mem::transmute::<usize, GetVersion>(address) };
+        let mut version = Version {
+            major: 0,
+            minor: 0,
+            patch: 0,
+        };
+        unsafe { get_version(&mut version.major, &mut version.minor, &mut version.patch) };
+        return Some(version);
+    }
+
+    let address = symbol("LLVMContextCreate\0")?;
+    let mut info: libc::Dl_info = unsafe { std::mem::zeroed() };
+    if unsafe { libc::dladdr(address as *const libc::c_void, &mut info) } == 0
+        || info.dli_fname.is_null()
+    {
+        return None;
+    }
+    let path = unsafe { CStr::from_ptr(info.dli_fname) }.to_string_lossy();
+    let file_name = path.rsplit('/').next()?;
+    parse_library_name(file_name)
+}
+
+#[cfg(not(unix))]
+fn library_version(_symbol: &dyn Fn(&str) -> Option<usize>) -> Option<Version> {
+    None
+}
+
+/// The address of `name`, which ends in a nul, in the LLVM in use.
+fn symbol(name: &str) -> Option<usize> {
+    #[cfg(LLVM_SYS_RUNTIME_LOADING)]
+    return crate::runtime::symbol(name);
+
+    #[cfg(all(not(LLVM_SYS_RUNTIME_LOADING), not(unix)))]
+    return {
+        let _ = name;
+        None
+    };
+
+    #[cfg(all(not(LLVM_SYS_RUNTIME_LOADING), unix))]
+    match unsafe { libc::dlsym(libc::RTLD_DEFAULT, name.as_ptr() as *const libc::c_char) } {
+        address if address.is_null() => None,
+        address => Some(address as usize),
+    }
+}
+
+/// The version in the file name of a shared LLVM library: `libLLVM-12.so`,
+/// `libLLVM-12.0.1.so`, `libLLVM.so.12` or `libLLVM-17.so.1`.
+fn parse_library_name(file_name: &str) -> Option<Version> {
+    let rest = file_name.strip_prefix("libLLVM")?;
+    let version = match rest.strip_prefix('-') {
+        Some(rest) => rest,
+        None => rest.strip_prefix(".so.")?,
+    };
+    let end = version
+        .find(|c: char| !c.is_ascii_digit() && c != '.')
+        .unwrap_or(version.len());
+    Version::parse(version[..end].trim_end_matches('.'))
+}
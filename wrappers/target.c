@@ -4,6 +4,22 @@
  * we can't bind directly to them (the function body is generated via macro),
  * so here are some wrappers.
  */
+#include <llvm/Config/llvm-config.h>
+
+/* When only some targets are linked, the build script puts filtered copies of
+ * the llvm/Config/*.def target lists first on the include path, and tells us
+ * whether the native target is among them. Without it, the Native* functions
+ * below report failure instead of referencing a backend that isn't linked.
+ */
+#ifdef LLVM_SYS_FEATURED_NO_NATIVE_TARGET
+#undef LLVM_NATIVE_TARGET
+#undef LLVM_NATIVE_TARGETINFO
+#undef LLVM_NATIVE_TARGETMC
+#undef LLVM_NATIVE_ASMPARSER
+#undef LLVM_NATIVE_ASMPRINTER
+#undef LLVM_NATIVE_DISASSEMBLER
+#endif
+
 #include <llvm-c/Target.h>
 
 void LLVM_InitializeAllTargetInfos(void) {